@@ -0,0 +1,97 @@
+use ggez::graphics::{Canvas, Rect};
+use ggez::{Context, GameResult};
+use log::debug;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::camera::Camera;
+use crate::difficulty::Difficulty;
+use crate::lander::LunarLander;
+use crate::particles::{Explosion, ExplosionPalette};
+use crate::terrain::{generate_terrain, Terrain, WORLD_WIDTH};
+use crate::theme::Theme;
+
+const DEMO_DIFFICULTY: Difficulty = Difficulty::Easy;
+const RESET_DELAY: f32 = 2.0; // seconds to linger on the outcome before the next demo
+
+/// The autopilot flying demo descents behind the title screen. Runs its own
+/// lander, terrain and camera so it never touches the real game's state.
+pub struct AttractMode {
+    seed: u64,
+    terrain: Terrain,
+    lander: LunarLander,
+    camera: Camera,
+    explosion: Option<Explosion>,
+    rng: StdRng,
+    reset_in: Option<f32>, // counting down once the demo lander has touched down
+}
+
+impl AttractMode {
+    pub fn new(seed: u64) -> Self {
+        let settings = DEMO_DIFFICULTY.settings();
+        AttractMode {
+            seed,
+            terrain: generate_terrain(seed, 1, &settings),
+            lander: LunarLander::new(WORLD_WIDTH / 2.0, 100.0, &settings),
+            camera: Camera::new(),
+            explosion: None,
+            rng: StdRng::seed_from_u64(seed),
+            reset_in: None,
+        }
+    }
+
+    /// Starts the next demo on fresh terrain.
+    fn reset(&mut self) {
+        *self = Self::new(self.seed.wrapping_add(1));
+        debug!("Attract demo seed: {}", self.seed);
+    }
+
+    /// Advances the demo by one physics tick.
+    pub fn step(&mut self) {
+        if let Some(explosion) = &mut self.explosion {
+            explosion.update();
+        }
+
+        if let Some(reset_in) = &mut self.reset_in {
+            *reset_in -= self.lander.physics.dt;
+            if *reset_in <= 0.0 {
+                self.reset();
+            }
+            return;
+        }
+
+        self.lander.autopilot_step(self.terrain.model());
+        self.lander.update();
+        if self.terrain.check_collision(&mut self.lander).is_some() {
+            if !self.lander.is_landed_safely() {
+                self.explosion = Some(Explosion::new(
+                    &mut self.rng,
+                    self.lander.position.x,
+                    self.lander.position.y,
+                    self.lander.physics.gravity,
+                    ExplosionPalette::default(),
+                ));
+            }
+            self.reset_in = Some(RESET_DELAY);
+        }
+
+        let altitude = self.terrain.height_at(self.lander.position.x) - self.lander.position.y;
+        self.camera.update(self.lander.position, altitude);
+    }
+
+    /// The world-space rectangle the demo is viewed through.
+    pub fn view(&self) -> Rect {
+        self.camera.view()
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, canvas: &mut Canvas, theme: &Theme) -> GameResult {
+        self.terrain.draw(ctx, canvas, theme)?;
+        if self.reset_in.is_none() || self.lander.is_landed_safely() {
+            self.lander.draw(ctx, canvas, theme, 1.0)?;
+        }
+        if let Some(explosion) = &mut self.explosion {
+            explosion.draw(ctx, canvas)?;
+        }
+        Ok(())
+    }
+}
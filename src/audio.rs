@@ -0,0 +1,87 @@
+use ggez::audio::{self, SoundSource, Source};
+use ggez::{Context, GameResult};
+use log::warn;
+
+const THRUST_SOUND: &str = "/thrust.ogg";
+const EXPLOSION_SOUND: &str = "/explosion.ogg";
+const LANDING_SOUND: &str = "/landing.ogg";
+const FLAMEOUT_SOUND: &str = "/flameout.ogg";
+
+/// Game sound effects. Any sound that fails to load is simply skipped, so a
+/// missing asset or broken audio device leaves the game silent, not crashed.
+#[derive(Default)]
+pub struct AudioState {
+    thrust: Option<Source>,
+    explosion: Option<Source>,
+    landing: Option<Source>,
+    flameout: Option<Source>,
+    muted: bool,
+}
+
+fn load(ctx: &mut Context, path: &str) -> Option<Source> {
+    match audio::Source::new(ctx, path) {
+        Ok(source) => Some(source),
+        Err(e) => {
+            warn!("Failed to load sound {}: {}", path, e);
+            None
+        }
+    }
+}
+
+impl AudioState {
+    pub fn new(ctx: &mut Context) -> Self {
+        let mut thrust = load(ctx, THRUST_SOUND);
+        if let Some(thrust) = &mut thrust {
+            thrust.set_repeat(true);
+        }
+
+        AudioState {
+            thrust,
+            explosion: load(ctx, EXPLOSION_SOUND),
+            landing: load(ctx, LANDING_SOUND),
+            flameout: load(ctx, FLAMEOUT_SOUND),
+            muted: false,
+        }
+    }
+
+    /// Starts or stops the looping engine sound.
+    pub fn set_thrusting(&mut self, ctx: &mut Context, thrusting: bool) -> GameResult {
+        let Some(thrust) = &mut self.thrust else {
+            return Ok(());
+        };
+        let audible = thrusting && !self.muted;
+        if audible && !thrust.playing() {
+            thrust.play(ctx)?;
+        } else if !audible && thrust.playing() {
+            thrust.stop(ctx)?;
+        }
+        Ok(())
+    }
+
+    pub fn play_explosion(&mut self, ctx: &mut Context) -> GameResult {
+        play_once(ctx, &mut self.explosion, self.muted)
+    }
+
+    pub fn play_landing(&mut self, ctx: &mut Context) -> GameResult {
+        play_once(ctx, &mut self.landing, self.muted)
+    }
+
+    pub fn play_flameout(&mut self, ctx: &mut Context) -> GameResult {
+        play_once(ctx, &mut self.flameout, self.muted)
+    }
+
+    pub fn toggle_mute(&mut self, ctx: &mut Context) -> GameResult {
+        self.muted = !self.muted;
+        if self.muted {
+            self.set_thrusting(ctx, false)?;
+        }
+        Ok(())
+    }
+}
+
+fn play_once(ctx: &mut Context, source: &mut Option<Source>, muted: bool) -> GameResult {
+    match source {
+        Some(source) if !muted => source.play_detached(ctx),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,71 @@
+use ggez::graphics::Rect;
+use ggez::mint::Point2;
+use glam::Vec2;
+
+use crate::screen::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::terrain::WORLD_WIDTH;
+
+const MAX_ZOOM: f32 = 2.5;
+const ZOOM_START_ALTITUDE: f32 = 200.0; // Start zooming in below this
+const ZOOM_FULL_ALTITUDE: f32 = 20.0; // Fully zoomed at or below this
+const SMOOTHING: f32 = 0.05; // Fraction of the gap closed per tick
+
+/// Maps world coordinates to the screen. It always scrolls sideways to keep
+/// the lander in view; in follow mode it also tracks height and zooms in as
+/// the ground gets close, otherwise it eases back to the unzoomed view.
+pub struct Camera {
+    center: Vec2,
+    zoom: f32,
+    follow: bool,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Camera {
+            center: Vec2::new(WORLD_WIDTH / 2.0, SCREEN_HEIGHT / 2.0),
+            zoom: 1.0,
+            follow: false,
+        }
+    }
+
+    pub fn toggle_follow(&mut self) {
+        self.follow = !self.follow;
+    }
+
+    /// Eases toward the target view; call once per physics tick.
+    pub fn update(&mut self, target: Point2<f32>, altitude: f32) {
+        let (target_center, target_zoom) = if self.follow {
+            let t = ((ZOOM_START_ALTITUDE - altitude) / (ZOOM_START_ALTITUDE - ZOOM_FULL_ALTITUDE))
+                .clamp(0.0, 1.0);
+            (Vec2::new(target.x, target.y), 1.0 + t * (MAX_ZOOM - 1.0))
+        } else {
+            (Vec2::new(target.x, SCREEN_HEIGHT / 2.0), 1.0)
+        };
+
+        // Jump rather than pan across the world when the lander wraps
+        if (target_center.x - self.center.x).abs() > WORLD_WIDTH / 2.0 {
+            self.center.x = target_center.x;
+        }
+
+        self.center += (target_center - self.center) * SMOOTHING;
+        self.zoom += (target_zoom - self.zoom) * SMOOTHING;
+    }
+
+    /// Where a point on the logical screen is in the world.
+    pub fn to_world(&self, screen: Point2<f32>) -> Point2<f32> {
+        let view = self.view();
+        Point2 {
+            x: view.x + screen.x * view.w / SCREEN_WIDTH,
+            y: view.y + screen.y * view.h / SCREEN_HEIGHT,
+        }
+    }
+
+    /// The world-space rectangle currently visible, kept inside the world.
+    pub fn view(&self) -> Rect {
+        let w = SCREEN_WIDTH / self.zoom;
+        let h = SCREEN_HEIGHT / self.zoom;
+        let x = (self.center.x - w / 2.0).clamp(0.0, WORLD_WIDTH - w);
+        let y = (self.center.y - h / 2.0).clamp(0.0, SCREEN_HEIGHT - h);
+        Rect::new(x, y, w, h)
+    }
+}
@@ -0,0 +1,9 @@
+use std::path::PathBuf;
+
+use directories::ProjectDirs;
+
+/// Location of a file in the platform config dir, if there is one.
+pub fn config_path(file_name: &str) -> Option<PathBuf> {
+    ProjectDirs::from("", "Christopher Brown", "Lunar Lander")
+        .map(|dirs| dirs.config_dir().join(file_name))
+}
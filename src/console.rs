@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+
+use ggez::graphics::{self, Canvas, Color, PxScale, Rect, Text, TextFragment};
+use ggez::{Context, GameResult};
+
+use crate::screen::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::theme::Theme;
+
+const LOG_LINES: usize = 8; // output lines kept and shown above the input
+const LINE_HEIGHT: f32 = 18.0;
+const TEXT_SIZE: f32 = 16.0;
+const MAX_INPUT: usize = 60; // characters
+
+const HELP: [&str; 4] = [
+    "set gravity <m/s²>   set thrust <m/s²>   set fuel <amount>",
+    "refuel               spawn <x> <y>",
+    "help                 ` or Esc closes",
+    "Tweaks apply to the current lander and stop replay recording.",
+];
+
+/// A physics value the console can set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tunable {
+    Gravity,
+    Thrust,
+    Fuel,
+}
+
+/// A parsed console command for the game to carry out.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Set(Tunable, f32),
+    Refuel,
+    Spawn(f32, f32),
+}
+
+impl Command {
+    fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let number = |word: &str| match word.parse::<f32>() {
+            Ok(value) if value.is_finite() && value >= 0.0 => Ok(value),
+            _ => Err(format!("'{}' is not a number of zero or more", word)),
+        };
+        match words.as_slice() {
+            ["set", name, value] => {
+                let tunable = match *name {
+                    "gravity" => Tunable::Gravity,
+                    "thrust" => Tunable::Thrust,
+                    "fuel" => Tunable::Fuel,
+                    _ => return Err(format!("Can't set '{}'", name)),
+                };
+                Ok(Command::Set(tunable, number(value)?))
+            }
+            ["refuel"] => Ok(Command::Refuel),
+            ["spawn", x, y] => Ok(Command::Spawn(number(x)?, number(y)?)),
+            _ => Err(format!("Unknown command '{}', try help", line)),
+        }
+    }
+}
+
+/// Developer console toggled with the backtick key, for tweaking physics
+/// while flying. Only available in debug builds or with `--console`.
+pub struct Console {
+    enabled: bool,
+    open: bool,
+    input: String,
+    log: VecDeque<String>,
+}
+
+impl Console {
+    pub fn new(enabled: bool) -> Self {
+        Console {
+            enabled,
+            open: false,
+            input: String::new(),
+            log: VecDeque::new(),
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Opens or closes the console, if it's enabled at all.
+    pub fn toggle(&mut self) {
+        self.open = self.enabled && !self.open;
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        // The toggle key arrives as text too
+        if c == '`' || c == '~' || c.is_control() || self.input.chars().count() >= MAX_INPUT {
+            return;
+        }
+        self.input.push(c);
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+    }
+
+    /// Takes the input line, echoing it to the log. Help and parse errors
+    /// are answered here; anything else is returned for the game to run.
+    pub fn submit(&mut self) -> Option<Command> {
+        let line = std::mem::take(&mut self.input);
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        self.print(format!("> {}", line));
+        if line == "help" {
+            for help in HELP {
+                self.print(help.to_string());
+            }
+            return None;
+        }
+        match Command::parse(line) {
+            Ok(command) => Some(command),
+            Err(e) => {
+                self.print(e);
+                None
+            }
+        }
+    }
+
+    pub fn print(&mut self, line: String) {
+        if self.log.len() == LOG_LINES {
+            self.log.pop_front();
+        }
+        self.log.push_back(line);
+    }
+
+    /// The log and input line in a panel along the bottom of the screen.
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, theme: &Theme) -> GameResult {
+        let height = (LOG_LINES + 1) as f32 * LINE_HEIGHT + 10.0;
+        let top = SCREEN_HEIGHT - height;
+        let panel = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            Rect::new(0.0, top, SCREEN_WIDTH, height),
+            Color::new(0.0, 0.0, 0.0, 0.75),
+        )?;
+        canvas.draw(&panel, graphics::DrawParam::default());
+
+        let first = LOG_LINES - self.log.len();
+        let lines = self
+            .log
+            .iter()
+            .cloned()
+            .chain(std::iter::once(format!("> {}_", self.input)));
+        for (i, line) in lines.enumerate() {
+            let text = Text::new(TextFragment::new(line).scale(PxScale::from(TEXT_SIZE)));
+            canvas.draw(
+                &text,
+                graphics::DrawParam::default()
+                    .dest([10.0, top + 5.0 + (first + i) as f32 * LINE_HEIGHT])
+                    .color(theme.hud_text),
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+use std::process::Command;
+
+/// A calendar day, in the player's local time zone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// Today's local date, so the daily rolls over at the player's midnight.
+    /// Falls back to UTC where the local offset can't be found.
+    pub fn today() -> Self {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs() as i64);
+        let local = secs + local_offset().unwrap_or(0);
+        Self::from_unix_days(local.div_euclid(86_400))
+    }
+
+    /// The civil date `days` after 1970-01-01 (Howard Hinnant's algorithm).
+    fn from_unix_days(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let doe = z.rem_euclid(146_097);
+        let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+        Date { year, month, day }
+    }
+
+    /// The level seed everyone plays on this date. Mixed (SplitMix64) so
+    /// neighbouring days don't get neighbouring terrain.
+    pub fn seed(self) -> u64 {
+        let mut z = (self.year as u64) * 10_000 + u64::from(self.month) * 100 + u64::from(self.day);
+        z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// The local time zone's current offset from UTC in seconds, as reported by
+/// the system `date` command. The standard library has no time zone support,
+/// and asking `date` stays free of unsafe code while still following DST.
+fn local_offset() -> Option<i64> {
+    let output = Command::new("date").arg("+%z").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_utc_offset(std::str::from_utf8(&output.stdout).ok()?.trim())
+}
+
+/// Seconds east of UTC for a `+hhmm` or `-hhmm` offset.
+fn parse_utc_offset(offset: &str) -> Option<i64> {
+    let sign = match offset.get(..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let digits = &offset[1..];
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i64 = digits[..2].parse().ok()?;
+    let minutes: i64 = digits[2..].parse().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Date {
+        Date { year, month, day }
+    }
+
+    #[test]
+    fn unix_day_zero_is_new_year_1970() {
+        assert_eq!(Date::from_unix_days(0), date(1970, 1, 1));
+    }
+
+    #[test]
+    fn unix_days_cross_a_leap_day() {
+        assert_eq!(Date::from_unix_days(11_016), date(2000, 2, 29));
+        assert_eq!(Date::from_unix_days(11_017), date(2000, 3, 1));
+        assert_eq!(Date::from_unix_days(20_088), date(2024, 12, 31));
+    }
+
+    #[test]
+    fn negative_unix_days_count_back_from_1970() {
+        assert_eq!(Date::from_unix_days(-1), date(1969, 12, 31));
+        // 1900 was not a leap year
+        assert_eq!(Date::from_unix_days(-25_508), date(1900, 3, 1));
+    }
+
+    #[test]
+    fn utc_offsets_parse_to_seconds() {
+        assert_eq!(parse_utc_offset("+0000"), Some(0));
+        assert_eq!(parse_utc_offset("+0530"), Some(19_800));
+        assert_eq!(parse_utc_offset("-0800"), Some(-28_800));
+        assert_eq!(parse_utc_offset("0800"), None);
+        assert_eq!(parse_utc_offset("+08"), None);
+        assert_eq!(parse_utc_offset(""), None);
+    }
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use crate::physics::PhysicsConfig;
+
+/// Preset chosen on the title screen.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+/// The tunables a `Difficulty` adjusts together.
+pub struct DifficultySettings {
+    pub fuel: f32, // tank capacity, full at spawn
+    pub physics: PhysicsConfig,
+    pub min_pad_width: usize, // in terrain points
+    pub max_pad_width: usize,
+}
+
+impl Difficulty {
+    pub fn settings(self) -> DifficultySettings {
+        match self {
+            Difficulty::Easy => DifficultySettings {
+                fuel: 150.0,
+                physics: PhysicsConfig {
+                    gravity: 1.2,
+                    max_safe_landing_velocity: 3.0,
+                    ..PhysicsConfig::lunar()
+                },
+                min_pad_width: 5,
+                max_pad_width: 8,
+            },
+            Difficulty::Normal => DifficultySettings {
+                fuel: 100.0,
+                physics: PhysicsConfig::lunar(),
+                min_pad_width: 3,
+                max_pad_width: 6,
+            },
+            Difficulty::Hard => DifficultySettings {
+                fuel: 70.0,
+                physics: PhysicsConfig {
+                    gravity: 2.0,
+                    max_safe_landing_velocity: 1.5,
+                    ..PhysicsConfig::lunar()
+                },
+                min_pad_width: 2,
+                max_pad_width: 4,
+            },
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn harder(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            _ => Difficulty::Hard,
+        }
+    }
+
+    pub fn easier(self) -> Self {
+        match self {
+            Difficulty::Hard => Difficulty::Normal,
+            _ => Difficulty::Easy,
+        }
+    }
+}
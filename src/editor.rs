@@ -0,0 +1,510 @@
+use std::path::Path;
+
+use ggez::graphics::{
+    self, Canvas, Color, DrawMode, Mesh, MeshBuilder, PxScale, Rect, Text, TextFragment,
+};
+use ggez::input::keyboard::KeyCode;
+use ggez::input::mouse::MouseButton;
+use ggez::mint::Point2;
+use ggez::{Context, GameResult};
+use log::{info, warn};
+
+use crate::level::{save_level, LevelDef, PadDef};
+use crate::physics::PhysicsConfig;
+use crate::screen::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::terrain::{level_terrain, Terrain, WORLD_WIDTH};
+use crate::theme::Theme;
+
+const GRID_SIZE: f32 = 20.0; // px between snap lines
+const GRAB_RADIUS: f32 = 8.0; // how close a click has to be to pick up a point
+const HANDLE_SIZE: f32 = 6.0;
+const MIN_POINT_GAP: f32 = 10.0; // px kept between neighbouring points
+const MIN_Y: f32 = 20.0; // points stay below the top of the world
+const SCROLL_STEP: f32 = 200.0; // px per arrow key press
+const NEW_LEVEL_HEIGHT: f32 = 450.0; // y of the flat ground a blank level starts with
+const NEW_LEVEL_FUEL: f32 = 100.0;
+const HELP_TOP: f32 = 10.0;
+
+/// Pad settings shared by every segment of one pad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct PadKind {
+    multiplier: u32,
+    refuel: bool,
+}
+
+/// Mouse-driven level editing. Points are dragged, added and removed, and
+/// runs of segments are marked as pads, which keeps them level. The result
+/// is a `LevelDef` that can be saved or played.
+pub struct Editor {
+    name: String,
+    gravity: f32,
+    wind: f32,
+    fuel: f32,
+    spawn: Point2<f32>,
+    points: Vec<Point2<f32>>,
+    pads: Vec<Option<PadKind>>, // per segment: points[i]..points[i + 1]
+    terrain: Terrain,           // rebuilt after each edit, for drawing
+    view_x: f32,
+    cursor: Point2<f32>, // world space
+    hovered: Option<usize>,
+    dragging: Option<usize>,
+    snap: bool,
+    message: String, // latest save or validation result
+}
+
+impl Editor {
+    /// Starts from `level`, or from flat ground with one pad in the middle.
+    pub fn new(level: Option<&LevelDef>) -> Self {
+        let level = level.cloned().unwrap_or_else(blank_level);
+        let points: Vec<Point2<f32>> = level.terrain.iter().map(|&p| Point2::from(p)).collect();
+        let mut pads = vec![None; points.len() - 1];
+        for pad in &level.pads {
+            for segment in &mut pads[pad.start..pad.end - 1] {
+                *segment = Some(PadKind {
+                    multiplier: pad.multiplier,
+                    refuel: pad.refuel,
+                });
+            }
+        }
+        Editor {
+            name: level.name.clone(),
+            gravity: level.gravity,
+            wind: level.wind,
+            fuel: level.fuel,
+            spawn: Point2::from(level.spawn),
+            points,
+            pads,
+            terrain: level_terrain(&level),
+            view_x: 0.0,
+            cursor: Point2 { x: 0.0, y: 0.0 },
+            hovered: None,
+            dragging: None,
+            snap: true,
+            message: String::new(),
+        }
+    }
+
+    /// The level as currently edited. Pads are the runs of marked segments,
+    /// split where their settings change.
+    pub fn level(&self) -> LevelDef {
+        let mut pads: Vec<PadDef> = Vec::new();
+        for (i, segment) in self.pads.iter().enumerate() {
+            let Some(kind) = segment else {
+                continue;
+            };
+            match pads.last_mut() {
+                Some(pad)
+                    if pad.end == i + 1
+                        && pad.multiplier == kind.multiplier
+                        && pad.refuel == kind.refuel =>
+                {
+                    pad.end = i + 2
+                }
+                _ => pads.push(PadDef {
+                    start: i,
+                    end: i + 2,
+                    multiplier: kind.multiplier,
+                    refuel: kind.refuel,
+                }),
+            }
+        }
+        LevelDef {
+            name: self.name.clone(),
+            gravity: self.gravity,
+            wind: self.wind,
+            fuel: self.fuel,
+            spawn: [self.spawn.x, self.spawn.y],
+            terrain: self.points.iter().map(|p| [p.x, p.y]).collect(),
+            pads,
+        }
+    }
+
+    /// Checks the level can be played, noting why not if it can't.
+    pub fn validated(&mut self) -> Option<LevelDef> {
+        let level = self.level();
+        match level.validate() {
+            Ok(()) => Some(level),
+            Err(e) => {
+                self.message = format!("Can't use level: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn save(&mut self, path: &Path) {
+        let Some(level) = self.validated() else {
+            return;
+        };
+        self.message = match save_level(path, &level) {
+            Ok(()) => {
+                info!("Saved level to {}", path.display());
+                format!("Saved to {}", path.display())
+            }
+            Err(e) => {
+                warn!("Failed to save level: {}", e);
+                format!("Save failed: {}", e)
+            }
+        };
+    }
+
+    /// The world-space rectangle being edited.
+    pub fn view(&self) -> Rect {
+        Rect::new(self.view_x, 0.0, SCREEN_WIDTH, SCREEN_HEIGHT)
+    }
+
+    /// Follows the mouse, given in logical screen units.
+    pub fn mouse_moved(&mut self, screen: Point2<f32>) {
+        self.cursor = Point2 {
+            x: screen.x + self.view_x,
+            y: screen.y,
+        };
+        if let Some(i) = self.dragging {
+            self.move_point(i, self.snapped(self.cursor));
+        } else {
+            self.hovered = self.point_near(self.cursor);
+        }
+    }
+
+    /// Left picks up a point, or adds one where there isn't; right marks or
+    /// unmarks the segment under the cursor as a pad.
+    pub fn mouse_down(&mut self, button: MouseButton, screen: Point2<f32>) {
+        self.mouse_moved(screen);
+        match button {
+            MouseButton::Left => {
+                self.dragging = self.hovered.or_else(|| self.insert_point(self.cursor));
+            }
+            MouseButton::Right => self.toggle_pad(),
+            _ => (),
+        }
+    }
+
+    pub fn mouse_up(&mut self) {
+        self.dragging = None;
+    }
+
+    pub fn press_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Left => self.scroll(-SCROLL_STEP),
+            KeyCode::Right => self.scroll(SCROLL_STEP),
+            KeyCode::G => self.snap = !self.snap,
+            KeyCode::P => {
+                self.spawn = self.snapped(self.cursor);
+                self.message = format!("Spawn at ({:.0}, {:.0})", self.spawn.x, self.spawn.y);
+            }
+            KeyCode::Delete | KeyCode::Back => self.remove_point(),
+            KeyCode::F => self.edit_pad(|kind| kind.refuel = !kind.refuel),
+            _ => {
+                if let Some(multiplier) = digit(key) {
+                    self.edit_pad(|kind| kind.multiplier = multiplier);
+                }
+            }
+        }
+    }
+
+    fn scroll(&mut self, dx: f32) {
+        let view_x = (self.view_x + dx).clamp(0.0, WORLD_WIDTH - SCREEN_WIDTH);
+        // The mouse stays put on screen, so it's now over another spot
+        self.cursor.x += view_x - self.view_x;
+        self.view_x = view_x;
+    }
+
+    fn snapped(&self, point: Point2<f32>) -> Point2<f32> {
+        if !self.snap {
+            return point;
+        }
+        Point2 {
+            x: (point.x / GRID_SIZE).round() * GRID_SIZE,
+            y: (point.y / GRID_SIZE).round() * GRID_SIZE,
+        }
+    }
+
+    fn point_near(&self, cursor: Point2<f32>) -> Option<usize> {
+        self.points.iter().position(|p| {
+            (p.x - cursor.x).abs() <= GRAB_RADIUS && (p.y - cursor.y).abs() <= GRAB_RADIUS
+        })
+    }
+
+    /// The segment spanning `x`.
+    fn segment_at(&self, x: f32) -> usize {
+        self.points
+            .partition_point(|p| p.x <= x)
+            .clamp(1, self.points.len() - 1)
+            - 1
+    }
+
+    /// Splits the segment under `at` with a new point, unless it would
+    /// crowd a neighbour. A point added inside a pad stays level with it.
+    fn insert_point(&mut self, at: Point2<f32>) -> Option<usize> {
+        let at = self.snapped(at);
+        let segment = self.segment_at(at.x);
+        let (left, right) = (self.points[segment], self.points[segment + 1]);
+        if at.x - left.x < MIN_POINT_GAP || right.x - at.x < MIN_POINT_GAP {
+            return None;
+        }
+        let y = if self.pads[segment].is_some() {
+            left.y
+        } else {
+            at.y.clamp(MIN_Y, SCREEN_HEIGHT - 1.0)
+        };
+        self.points.insert(segment + 1, Point2 { x: at.x, y });
+        self.pads.insert(segment + 1, self.pads[segment]);
+        self.rebuild();
+        Some(segment + 1)
+    }
+
+    /// Drops the hovered point, joining its two segments. The ends stay.
+    fn remove_point(&mut self) {
+        let Some(i) = self.hovered else {
+            return;
+        };
+        if i == 0 || i == self.points.len() - 1 {
+            return;
+        }
+        self.points.remove(i);
+        let joined = self.pads[i - 1].and(self.pads[i]);
+        self.pads.remove(i);
+        self.pads[i - 1] = joined;
+        self.hovered = None;
+        self.level_pad(i - 1);
+        self.rebuild();
+    }
+
+    /// Moves point `i` between its neighbours; the ends only move up and
+    /// down. Any pad it belongs to moves up and down with it.
+    fn move_point(&mut self, i: usize, to: Point2<f32>) {
+        let x = if i == 0 {
+            0.0
+        } else if i == self.points.len() - 1 {
+            WORLD_WIDTH
+        } else {
+            to.x.clamp(
+                self.points[i - 1].x + MIN_POINT_GAP,
+                self.points[i + 1].x - MIN_POINT_GAP,
+            )
+        };
+        self.points[i] = Point2 {
+            x,
+            y: to.y.clamp(MIN_Y, SCREEN_HEIGHT - 1.0),
+        };
+        if i > 0 && self.pads[i - 1].is_some() {
+            self.level_pad_to(i - 1, self.points[i].y);
+        } else if i < self.pads.len() && self.pads[i].is_some() {
+            self.level_pad_to(i, self.points[i].y);
+        }
+        self.rebuild();
+    }
+
+    /// Marks the segment under the cursor as pad, joining any pad next to
+    /// it, or unmarks it.
+    fn toggle_pad(&mut self) {
+        let segment = self.segment_at(self.cursor.x);
+        self.pads[segment] = match self.pads[segment] {
+            Some(_) => None,
+            None => {
+                let neighbour = segment
+                    .checked_sub(1)
+                    .and_then(|left| self.pads[left])
+                    .or_else(|| self.pads.get(segment + 1).copied().flatten());
+                Some(neighbour.unwrap_or(PadKind {
+                    multiplier: 1,
+                    refuel: false,
+                }))
+            }
+        };
+        self.level_pad(segment);
+        self.rebuild();
+    }
+
+    /// Applies `change` to the whole pad under the cursor.
+    fn edit_pad(&mut self, change: impl Fn(&mut PadKind)) {
+        let segment = self.segment_at(self.cursor.x);
+        if self.pads[segment].is_none() {
+            return;
+        }
+        let (start, end) = self.pad_run(segment);
+        for kind in self.pads[start..end].iter_mut().flatten() {
+            change(kind);
+        }
+        self.rebuild();
+    }
+
+    /// The run of pad segments around `segment`, as `start..end`.
+    fn pad_run(&self, segment: usize) -> (usize, usize) {
+        let mut start = segment;
+        while start > 0 && self.pads[start - 1].is_some() {
+            start -= 1;
+        }
+        let mut end = segment + 1;
+        while end < self.pads.len() && self.pads[end].is_some() {
+            end += 1;
+        }
+        (start, end)
+    }
+
+    /// Flattens the pad through `segment`, if there is one, to its left end.
+    fn level_pad(&mut self, segment: usize) {
+        if self.pads[segment].is_some() {
+            let (start, _) = self.pad_run(segment);
+            self.level_pad_to(segment, self.points[start].y);
+        }
+    }
+
+    fn level_pad_to(&mut self, segment: usize, y: f32) {
+        let (start, end) = self.pad_run(segment);
+        for point in &mut self.points[start..=end] {
+            point.y = y;
+        }
+    }
+
+    fn rebuild(&mut self) {
+        self.terrain = level_terrain(&self.level());
+    }
+
+    /// Draws the level in world space: terrain, grid, point handles and the
+    /// spawn point.
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, theme: &Theme) -> GameResult {
+        let mut mb = MeshBuilder::new();
+        if self.snap {
+            let faint = Color {
+                a: 0.08,
+                ..theme.hud_text
+            };
+            let mut x = (self.view_x / GRID_SIZE).floor() * GRID_SIZE;
+            while x <= self.view_x + SCREEN_WIDTH {
+                mb.line(
+                    &[
+                        Point2 { x, y: 0.0 },
+                        Point2 {
+                            x,
+                            y: SCREEN_HEIGHT,
+                        },
+                    ],
+                    1.0,
+                    faint,
+                )?;
+                x += GRID_SIZE;
+            }
+            let mut y = 0.0;
+            while y <= SCREEN_HEIGHT {
+                mb.line(
+                    &[
+                        Point2 { x: self.view_x, y },
+                        Point2 {
+                            x: self.view_x + SCREEN_WIDTH,
+                            y,
+                        },
+                    ],
+                    1.0,
+                    faint,
+                )?;
+                y += GRID_SIZE;
+            }
+            canvas.draw(
+                &Mesh::from_data(ctx, mb.build()),
+                graphics::DrawParam::default(),
+            );
+            mb = MeshBuilder::new();
+        }
+
+        self.terrain.draw(ctx, canvas, theme)?;
+
+        for (i, point) in self.points.iter().enumerate() {
+            let color = if Some(i) == self.dragging || Some(i) == self.hovered {
+                Color::YELLOW
+            } else {
+                theme.hud_text
+            };
+            mb.rectangle(
+                DrawMode::stroke(1.5),
+                Rect::new(
+                    point.x - HANDLE_SIZE / 2.0,
+                    point.y - HANDLE_SIZE / 2.0,
+                    HANDLE_SIZE,
+                    HANDLE_SIZE,
+                ),
+                color,
+            )?;
+        }
+        mb.circle(DrawMode::stroke(2.0), self.spawn, 10.0, 0.5, Color::CYAN)?;
+        canvas.draw(
+            &Mesh::from_data(ctx, mb.build()),
+            graphics::DrawParam::default(),
+        );
+
+        let label = Text::new(TextFragment::new("SPAWN").scale(PxScale::from(12.0)));
+        canvas.draw(
+            &label,
+            graphics::DrawParam::default()
+                .dest([self.spawn.x, self.spawn.y - 18.0])
+                .offset([0.5, 0.5])
+                .color(Color::CYAN),
+        );
+        Ok(())
+    }
+
+    /// Controls and the latest message, in screen space.
+    pub fn draw_help(&self, canvas: &mut Canvas, theme: &Theme) {
+        let snap = if self.snap { "on" } else { "off" };
+        let lines = [
+            format!(
+                "LEVEL EDITOR   x {:.0}  y {:.0}",
+                self.cursor.x, self.cursor.y
+            ),
+            "Click: add/drag point   Right-click: toggle pad   Del: remove point".to_string(),
+            format!(
+                "P: spawn here   F: refuel pad   1-9: pad multiplier   G: grid snap ({})",
+                snap
+            ),
+            "Left/Right: scroll   S: save   Enter: play   Esc: menu".to_string(),
+            self.message.clone(),
+        ];
+        for (i, line) in lines.into_iter().enumerate() {
+            let text = Text::new(TextFragment::new(line).scale(PxScale::from(16.0)));
+            canvas.draw(
+                &text,
+                graphics::DrawParam::default()
+                    .dest([10.0, HELP_TOP + i as f32 * 20.0])
+                    .color(theme.hud_text),
+            );
+        }
+    }
+}
+
+/// Flat ground across the world with a pad in the middle.
+fn blank_level() -> LevelDef {
+    let terrain: Vec<[f32; 2]> = (0..=12)
+        .map(|i| [i as f32 * WORLD_WIDTH / 12.0, NEW_LEVEL_HEIGHT])
+        .collect();
+    LevelDef {
+        name: "Custom".to_string(),
+        gravity: PhysicsConfig::lunar().gravity,
+        wind: 0.0,
+        fuel: NEW_LEVEL_FUEL,
+        spawn: [WORLD_WIDTH / 2.0, 100.0],
+        terrain,
+        pads: vec![PadDef {
+            start: 6,
+            end: 8,
+            multiplier: 1,
+            refuel: false,
+        }],
+    }
+}
+
+fn digit(key: KeyCode) -> Option<u32> {
+    let digits = [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+    ];
+    digits
+        .iter()
+        .position(|&digit| digit == key)
+        .map(|i| i as u32 + 1)
+}
@@ -1,175 +1,2460 @@
+use std::cell::OnceCell;
+use std::collections::HashSet;
+
 use ggez::event::EventHandler;
 use ggez::graphics::{self, Canvas, Color, PxScale, Text, TextFragment};
+use ggez::input::gamepad::gilrs::{Axis, Button};
+use ggez::input::gamepad::GamepadId;
 use ggez::input::keyboard::{KeyCode, KeyInput};
+use ggez::input::mouse::MouseButton;
 use ggez::mint::Point2;
 use ggez::{Context, GameResult};
-use log::debug;
-use rand::Rng;
+use glam::Vec2;
+use log::{debug, info, warn};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::attract::AttractMode;
+use crate::audio::AudioState;
+use crate::camera::Camera;
+use crate::config::config_path;
+use crate::console::{Command, Console, Tunable};
+use crate::daily::Date;
+use crate::difficulty::Difficulty;
+use crate::editor::Editor;
+use crate::ghost::{Ghost, GhostRun, InstantReplay, Recorder};
+use crate::highscore::{load_daily_best, load_high_score, save_daily_best, save_high_score};
+use crate::keybindings::{Action, KeyBindings};
+use crate::lander::{
+    draw_arrow, ideal_descent_fuel, CeilingMode, EdgeMode, LunarLander, ThrustCurve,
+};
+use crate::level::LevelDef;
+use crate::meteor::MeteorShower;
+use crate::particles::{ExhaustEmitter, Explosion};
+use crate::physics::PhysicsConfig;
+use crate::powerup::{PowerUp, BOOST_DURATION};
+use crate::replay::{Replay, ReplayPlayer, ReplayRecorder, REPLAY_FILE};
+use crate::screen::{fit_view, letterbox, window_to_logical, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::settings::{ControlScheme, Settings};
+use crate::telemetry::{log_outcome, LandingResult, Outcome};
+use crate::terrain::{
+    generate_cave_terrain, generate_terrain, level_terrain, ContactPart, Terrain, WORLD_WIDTH,
+};
+use crate::theme::Theme;
+use crate::timestep::FixedTimestep;
+use crate::wind::Wind;
+
+const LOW_ALTITUDE: f32 = 50.0;
+const LOW_FUEL: f32 = 25.0; // percent of the tank
+const CRITICAL_FUEL: f32 = 10.0;
+const OUT_OF_FUEL_BANNER_FRAMES: u64 = 120;
+const STICK_ROTATION_RATE: f32 = 0.01; // rotate() amount per tick at full deflection
+const KEY_ROTATION_RATE: f32 = 0.02; // rotate() amount per tick while a rotate key is held
+const FUEL_BAR_WIDTH: f32 = 150.0;
+const FUEL_BAR_HEIGHT: f32 = 12.0;
+const DRIFT_GAUGE_WIDTH: f32 = 240.0;
+const DRIFT_GAUGE_HEIGHT: f32 = 12.0;
+const DRIFT_GAUGE_TOP: f32 = SCREEN_HEIGHT - 30.0;
+const DRIFT_GAUGE_RANGE: f32 = 10.0; // m/s either side of center
+const DEBUG_OVERLAY_TOP: f32 = 310.0; // below the HUD's left column
+const MINIMAP_SCALE: f32 = 1.0 / 12.0; // whole world fits in 200x50
+
+// Top-left corner on screen, centered horizontally
+const MINIMAP_ORIGIN: [f32; 2] = [(SCREEN_WIDTH - WORLD_WIDTH * MINIMAP_SCALE) / 2.0, 10.0];
+
+const TIME_LIMIT: f32 = 60.0; // seconds per level in timed mode
+const TIME_BONUS_PER_SECOND: f32 = 20.0;
+const ROUGH_LANDING_FACTOR: f32 = 0.1; // share of the score kept off a pad
+const TUTORIAL_TOP: f32 = 320.0; // below the HUD's right column
+const SETTINGS_ROWS: usize = 7;
+const SPAWN: [f32; 2] = [WORLD_WIDTH / 2.0, 100.0]; // fixed start, over the cave shaft
+const SPAWN_MARGIN: f32 = 100.0; // random spawns stay this far from the side walls
+const SPAWN_HEIGHT: (f32, f32) = (60.0, 140.0); // range of random spawn y
+const SPAWN_CLEARANCE: f32 = 40.0; // minimum gap above the ground beneath a spawn
+const SPAWN_SEED_SALT: u64 = 0x5EED_5B0A; // keeps spawn rolls apart from terrain rolls
+const REROLL_SEED_STRIDE: u64 = 0x9E37_79B9_7F4A_7C15; // keeps rerolled seeds off the level sequence
+const SUMMARY_DELAY: f32 = 1.5; // seconds the outcome plays out before the summary
+const SUMMARY_PANEL: [f32; 4] = [200.0, 130.0, 400.0, 340.0]; // x, y, w, h
+const DAILY_DIFFICULTY: Difficulty = Difficulty::Normal; // same for everyone, so scores compare
+const EDITOR_FILE: &str = "level.toml"; // where the editor saves, in the config dir
+const STARTING_LIVES: u32 = 3;
+const CAVE_LEVEL_INTERVAL: u32 = 4; // every Nth level is roofed over
+const LEVEL_REFUEL: f32 = 30.0; // fuel topped up when advancing a level
+const DOCKING_SPEED: f32 = 0.5; // m/s; slower safe landings on a refuel pad dock
+const REFUEL_RATE: f32 = 0.5; // fuel per tick while docked at a refuel pad
+const BOUNCE_DAMPING: f32 = 0.4; // share of vertical speed kept by a bounce
+const MAX_BOUNCES: u32 = 2; // a hard touchdown after this many bounces crashes
+const SHAKE_DURATION: f32 = 0.5; // seconds
+const SHAKE_PER_SPEED: f32 = 2.0; // pixels of jitter per m/s of impact
+const MAX_SHAKE: f32 = 15.0;
+const SLOW_MO_ALTITUDE: f32 = 80.0; // slow-motion eases in below this height
+const SLOW_MO_SPEED: f32 = 4.0; // m/s; only for a controlled approach
+const MIN_TIME_SCALE: f32 = 0.3; // never slower than this
+const TRAIL_LENGTH: usize = 60; // positions kept, one per physics tick
+const POWER_UP_RESPAWN: Option<f32> = None; // one-shot per attempt; Some(seconds) to respawn
+
+// Star layers from far to near: (scroll fraction of camera speed, radius, brightness)
+const STAR_LAYERS: [(f32, f32, f32); 3] = [(0.1, 0.7, 0.45), (0.2, 1.0, 0.7), (0.4, 1.5, 1.0)];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GameState {
+    Menu,
+    Settings,
+    Editor,
+    Playing,
+    GameOver,
+    Summary, // end-of-attempt panel, shown once the GameOver pause is up
+}
+
+/// How the HUD reports the lander's velocity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HudMode {
+    /// The raw (x, y) vector, in world terms.
+    Vector,
+    /// Overall speed and sideways drift. Descent is shown either way.
+    Rates,
+}
+
+/// Choices on the summary panel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SummaryOption {
+    Next,
+    Retry,
+    PlayAgain,
+    Menu,
+}
+
+impl SummaryOption {
+    fn label(self) -> &'static str {
+        match self {
+            SummaryOption::Next => "Next level",
+            SummaryOption::Retry => "Retry",
+            SummaryOption::PlayAgain => "Play again",
+            SummaryOption::Menu => "Menu",
+        }
+    }
+}
 
-use crate::lander::LunarLander;
-use crate::particles::Explosion;
-use crate::terrain::{generate_terrain, Terrain};
+/// How a landing's points were made up, for the summary panel.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct ScoreBreakdown {
+    fuel: f32,
+    velocity: f32,
+    attitude: f32,
+    time: f32,
+    factor: f32, // 1 on a pad, ROUGH_LANDING_FACTOR off one
+    multiplier: u32,
+    total: u32,
+}
+
+/// What one attempt at a level accumulates, all dropped together whenever
+/// a fresh lander is put in.
+#[derive(Debug, Default, PartialEq)]
+struct Attempt {
+    elapsed: f32, // seconds of flight; frozen on pause and game over
+    out_of_fuel_frame: Option<u64>,
+    landed_pad: Option<usize>, // pad the lander is resting on mid-level
+    clearing_surface: bool,    // just lifted off; ignore contacts until clear
+    visited_pads: Vec<usize>,
+    bounces: u32,                         // hard-but-survivable touchdowns
+    game_over_time: f32,                  // seconds since the attempt ended
+    last_landing: Option<ScoreBreakdown>, // latest scored touchdown
+    shake_timer: f32,                     // seconds of screen shake left
+}
 
 pub struct MainState {
     lander: LunarLander,
     terrain: Terrain,
-    stars: Vec<Point2<f32>>,
-    game_over: bool,
-    explosion: Option<Explosion>,
+    stars: Vec<Star>,
+    star_mesh: OnceCell<graphics::Mesh>, // built on first draw
+    seed: u64,
+    difficulty: Difficulty,
+    drag: f32,
+    wind: Wind,
+    meteors: MeteorShower,
+    power_up: PowerUp,
+    attract: AttractMode, // autopilot demo behind the title screen
+    instant_rotation: bool,
+    assist: bool,
+    thrust_curve: ThrustCurve, // this session's; new sessions take it from settings
+    random_spawn: bool,        // likewise
+    spawn: Point2<f32>,        // where this level's attempts start
+    edge_mode: EdgeMode,
+    ceiling_mode: CeilingMode,
+    show_velocity_vector: bool,
+    show_landing_reticle: bool,
+    show_trail: bool,
+    show_pad_highlight: bool,
+    show_approach_corridor: bool,
+    hud_mode: HudMode,
+    slow_motion: bool,
+    time_scale: f32, // 1.0 at full speed; below while slow-motion is active
+    timed: bool,
+    stick_rotation: f32,
+    mouse_position: Option<Point2<f32>>, // logical screen units, for mouse aim
+    mouse_thrust: bool,                  // left button held under mouse aim
+    bindings: KeyBindings,
+    settings: Settings,
+    settings_row: usize,       // highlighted row on the settings screen
+    letterbox: graphics::Rect, // logical rect covering the whole window
+    show_debug: bool,
+    ticks_last_frame: u32, // physics ticks run by the latest update, for the overlay
+    audio: AudioState,
+    camera: Camera,
+    state: GameState,
+    paused: bool,
+    score: u32,
+    lives: u32,
+    level: u32,
+    level_fuel: f32, // fuel the lander starts this level with
+    high_score: u32,
+    daily: Option<Date>, // day of the daily challenge being played, if it is one
+    daily_best: u32,
+    custom_level: Option<LevelDef>, // hand-made level from the command line, played instead of generated ones
+    session_level: Option<LevelDef>, // custom level the current session plays, if any
+    editor: Editor,
+    console: Console,
+    frame: u64,
+    timestep: FixedTimestep,
+    attempt: Attempt,
+    autopilot: bool,
+    touch_and_go: bool, // safe pad landings keep playing until every pad is visited
+    summary_row: usize, // highlighted summary option
+    shake_magnitude: f32, // jitter in pixels when the shake starts
+    explosions: Vec<Explosion>, // crash blasts and dust, until they burn out
+    recorder: Recorder,
+    instant_replay: InstantReplay, // last few seconds, for watching a crash again
+    session_seed: u64,             // level seeds derive from this, so a session can be replayed
+    rerolls: u64,                  // fresh terrains asked for on the current level
+    particle_rng: StdRng,          // seeded with the session so effects replay identically
+    session_tick: u64,             // physics ticks since the session started
+    held_keys: HashSet<KeyCode>,
+    replay_recorder: Option<ReplayRecorder>,
+    replay_player: Option<ReplayPlayer>,
+    ghost: Option<Ghost>, // best landing so far, replayed alongside each attempt
+    exhaust: ExhaustEmitter,
 }
 
 impl MainState {
-    pub fn new(ctx: &mut Context) -> GameResult<MainState> {
-        let terrain = generate_terrain(ctx)?;
-        let stars = generate_stars();
+    pub fn new(
+        ctx: &mut Context,
+        custom_level: Option<LevelDef>,
+        console: bool,
+    ) -> GameResult<MainState> {
+        let (width, height) = ctx.gfx.drawable_size();
+        Ok(Self::build(
+            custom_level,
+            console,
+            AudioState::new(ctx),
+            letterbox(width, height),
+        ))
+    }
 
-        Ok(MainState {
-            lander: LunarLander::new(400.0, 100.0),
+    /// Everything `new` sets up besides loading sounds and reading the window
+    /// size, so a game can be built without a window.
+    fn build(
+        custom_level: Option<LevelDef>,
+        console: bool,
+        audio: AudioState,
+        letterbox: graphics::Rect,
+    ) -> MainState {
+        let seed = rand::random();
+        info!("Level seed: {}", seed);
+        let difficulty = Difficulty::Normal;
+        let terrain = generate_terrain(seed, 1, &difficulty.settings());
+        let power_up = PowerUp::for_level(seed, terrain.model(), POWER_UP_RESPAWN);
+        let stars = generate_stars(seed);
+        let editor = Editor::new(custom_level.as_ref());
+        let drag = 0.0; // Vacuum by default
+        let mut lander = LunarLander::new(SPAWN[0], SPAWN[1], &difficulty.settings());
+        lander.set_drag(drag);
+
+        MainState {
+            lander,
             terrain,
             stars,
-            game_over: false,
-            explosion: None,
-        })
+            star_mesh: OnceCell::new(),
+            seed,
+            difficulty,
+            drag,
+            wind: Wind::calm(),
+            meteors: MeteorShower::for_level(seed, 1, PhysicsConfig::lunar().gravity),
+            power_up,
+            attract: AttractMode::new(seed),
+            instant_rotation: false,
+            assist: false,
+            thrust_curve: ThrustCurve::Linear,
+            random_spawn: false,
+            spawn: Point2::from(SPAWN),
+            edge_mode: EdgeMode::Clamp,
+            ceiling_mode: CeilingMode::default(),
+            show_velocity_vector: true,
+            show_landing_reticle: false,
+            show_trail: false,
+            show_pad_highlight: true,
+            show_approach_corridor: false,
+            hud_mode: HudMode::Vector,
+            slow_motion: false,
+            time_scale: 1.0,
+            timed: false,
+            stick_rotation: 0.0,
+            mouse_position: None,
+            mouse_thrust: false,
+            bindings: KeyBindings::load(),
+            settings: Settings::load(),
+            settings_row: 0,
+            show_debug: false,
+            ticks_last_frame: 0,
+            letterbox,
+            audio,
+            camera: Camera::new(),
+            state: GameState::Menu,
+            paused: false,
+            score: 0,
+            lives: STARTING_LIVES,
+            level: 1,
+            level_fuel: 100.0,
+            high_score: load_high_score(),
+            daily: None,
+            daily_best: 0,
+            custom_level,
+            session_level: None,
+            editor,
+            frame: 0,
+            timestep: FixedTimestep::new(PhysicsConfig::lunar().dt),
+            attempt: Attempt::default(),
+            autopilot: false,
+            touch_and_go: false,
+            summary_row: 0,
+            shake_magnitude: 0.0,
+            explosions: Vec::new(),
+            recorder: Recorder::default(),
+            instant_replay: InstantReplay::default(),
+            session_seed: seed,
+            rerolls: 0,
+            particle_rng: StdRng::seed_from_u64(seed),
+            session_tick: 0,
+            held_keys: HashSet::new(),
+            replay_recorder: None,
+            replay_player: None,
+            ghost: GhostRun::load().map(Ghost::new),
+            exhaust: ExhaustEmitter::new(PhysicsConfig::lunar().gravity),
+            console: Console::new(console),
+        }
+    }
+
+    /// Itemizes the points for the current touchdown on `pad`, adding any
+    /// time bonus and the pad's multiplier to `landing_score`.
+    fn compute_landing_score(&self, pad: Option<usize>) -> ScoreBreakdown {
+        let time_score = if self.timed {
+            self.time_remaining() * TIME_BONUS_PER_SECOND
+        } else {
+            0.0
+        };
+        let multiplier = pad.map_or(1, |pad| self.terrain.pads()[pad].multiplier);
+        landing_score(&self.lander, time_score, multiplier)
+    }
+
+    fn time_remaining(&self) -> f32 {
+        (TIME_LIMIT - self.attempt.elapsed).max(0.0)
+    }
+
+    /// Starts a new run from level 1 with full lives and fuel.
+    fn start_game(&mut self) {
+        self.replay_player = None;
+        self.daily = None;
+        self.session_level = self.custom_level.clone();
+        self.thrust_curve = self.settings.thrust_curve;
+        self.random_spawn = self.settings.random_spawn;
+        self.start_session(rand::random());
+    }
+
+    /// Starts today's daily challenge: a run seeded from the local date, so
+    /// everyone gets the same levels that day.
+    fn start_daily(&mut self) {
+        let date = Date::today();
+        info!("Daily challenge for {}", date);
+        self.replay_player = None;
+        self.daily = Some(date);
+        self.daily_best = load_daily_best(date);
+        self.session_level = None;
+        self.difficulty = DAILY_DIFFICULTY;
+        self.thrust_curve = self.settings.thrust_curve;
+        self.random_spawn = self.settings.random_spawn;
+        self.start_session(date.seed());
+    }
+
+    /// Starts a run whose levels are all derived from `seed`, recording it
+    /// so it can be saved as a replay.
+    fn start_session(&mut self, seed: u64) {
+        self.session_seed = seed;
+        self.particle_rng = StdRng::seed_from_u64(seed);
+        self.session_tick = 0;
+        self.held_keys.clear();
+        self.replay_recorder = Some(ReplayRecorder::new(Replay {
+            seed,
+            difficulty: self.difficulty,
+            edge_mode: self.edge_mode,
+            ceiling_mode: self.ceiling_mode,
+            instant_rotation: self.instant_rotation,
+            touch_and_go: self.touch_and_go,
+            timed: self.timed,
+            assist: self.assist,
+            autopilot: self.autopilot,
+            thrust_curve: self.thrust_curve,
+            random_spawn: self.random_spawn,
+            level: self.session_level.clone(),
+            events: Vec::new(),
+        }));
+        self.score = 0;
+        self.lives = STARTING_LIVES;
+        self.level = 1;
+        self.rerolls = 0;
+        self.level_fuel = self
+            .session_level
+            .as_ref()
+            .map_or(self.difficulty.settings().fuel, |level| level.fuel);
+        self.load_level();
+    }
+
+    /// Advances to a harder level, carrying leftover fuel plus a top-up.
+    fn next_level(&mut self) {
+        self.level += 1;
+        self.rerolls = 0;
+        self.level_fuel = (self.lander.fuel + LEVEL_REFUEL).min(self.lander.max_fuel);
+        info!("Advancing to level {}", self.level);
+        self.load_level();
+    }
+
+    /// Swaps the current level's terrain and stars for a fresh roll, keeping
+    /// the level number, and starts over on it.
+    fn new_level(&mut self) {
+        self.rerolls += 1;
+        info!("New terrain for level {}", self.level);
+        self.load_level();
+    }
+
+    /// Rolls fresh terrain for the current level and drops a new lander into it.
+    fn load_level(&mut self) {
+        self.seed = self
+            .session_seed
+            .wrapping_add(u64::from(self.level - 1))
+            .wrapping_add(self.rerolls.wrapping_mul(REROLL_SEED_STRIDE));
+        info!("Level seed: {}", self.seed);
+        let settings = self.difficulty.settings();
+        if let Some(level) = &self.session_level {
+            info!("Custom level: {}", level.name);
+            self.terrain = level_terrain(level);
+            self.spawn = Point2::from(level.spawn);
+            self.wind = Wind::steady(level.wind);
+            // Hand-made levels only have the hazards they describe
+            self.meteors = MeteorShower::for_level(self.seed, 1, level.gravity);
+        } else {
+            let cave = self.level.is_multiple_of(CAVE_LEVEL_INTERVAL);
+            self.terrain = if cave {
+                info!("Cave level");
+                generate_cave_terrain(self.seed, self.level, &settings)
+            } else {
+                generate_terrain(self.seed, self.level, &settings)
+            };
+            // Caves are only open above the fixed spawn
+            self.spawn = if self.random_spawn && !cave {
+                self.random_spawn_point()
+            } else {
+                Point2::from(SPAWN)
+            };
+            self.wind = Wind::for_level(self.seed, self.level);
+            self.meteors = MeteorShower::for_level(self.seed, self.level, settings.physics.gravity);
+        }
+        self.stars = generate_stars(self.seed);
+        self.stick_rotation = 0.0;
+        self.reset_lander();
+    }
+
+    /// A seeded spot along the top of the level, kept clear of the ground
+    /// under the lander's full width.
+    fn random_spawn_point(&self) -> Point2<f32> {
+        let mut rng = StdRng::seed_from_u64(self.seed ^ SPAWN_SEED_SALT);
+        let x = rng.gen_range(SPAWN_MARGIN..WORLD_WIDTH - SPAWN_MARGIN);
+        let ground = [-SPAWN_CLEARANCE, 0.0, SPAWN_CLEARANCE]
+            .iter()
+            .map(|dx| self.terrain.height_at(x + dx))
+            .fold(f32::INFINITY, f32::min);
+        let y = rng
+            .gen_range(SPAWN_HEIGHT.0..SPAWN_HEIGHT.1)
+            .min(ground - SPAWN_CLEARANCE);
+        info!("Spawning at ({:.0}, {:.0})", x, y);
+        Point2 { x, y }
+    }
+
+    /// Puts a fresh lander back at the top of the current terrain. The one
+    /// place per-attempt state is reset, whether retrying, restarting from
+    /// game over, or loading a level.
+    fn reset_lander(&mut self) {
+        self.lander = self.spawn_lander();
+        // Don't leave the last attempt's plume hanging in the air
+        self.exhaust = ExhaustEmitter::new(self.lander.physics.gravity);
+        self.power_up = PowerUp::for_level(self.seed, self.terrain.model(), POWER_UP_RESPAWN);
+        self.attempt = Attempt::default();
+        self.explosions.clear();
+        self.state = GameState::Playing;
+        self.recorder.clear();
+        self.instant_replay.clear();
+        if let Some(ghost) = &mut self.ghost {
+            ghost.restart();
+        }
+    }
+
+    /// Title, settings or editor screen, where key presses aren't part of a
+    /// session.
+    fn in_menu(&self) -> bool {
+        matches!(
+            self.state,
+            GameState::Menu | GameState::Settings | GameState::Editor
+        )
+    }
+
+    fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        debug!("Paused: {}", self.paused);
+        // Key releases are ignored while paused, so don't leave engines latched
+        self.lander.set_thrust_input(0.0);
+        self.lander.apply_rcs(0.0);
+        self.stick_rotation = 0.0;
+    }
+
+    /// Advances the simulation by one fixed physics tick.
+    fn step(&mut self) {
+        self.frame += 1;
+        self.session_tick += 1;
+
+        if self.state == GameState::Menu {
+            self.attract.step();
+        }
+
+        if self.state == GameState::Playing {
+            self.recorder.record(&self.lander);
+            self.instant_replay.record(&self.lander);
+            if let Some(ghost) = &mut self.ghost {
+                ghost.advance();
+            }
+        }
+
+        // Held controls act every tick, independent of the OS key-repeat rate
+        if self.state == GameState::Playing {
+            let thrust = held_thrust(&self.held_keys, &self.bindings);
+            if thrust > 0.0 {
+                self.lander.set_thrust_input(thrust);
+            } else if self.mouse_thrust {
+                self.stop_recording("mouse aim");
+                self.lander.set_thrust_input(1.0);
+            }
+        }
+
+        if let (GameState::Playing, Some(pad)) = (self.state, self.attempt.landed_pad) {
+            if self.lander.thrust > 0.0 && self.lander.fuel > 0.0 {
+                debug!("Lifting off from pad {}", pad);
+                self.attempt.landed_pad = None;
+                self.attempt.clearing_surface = true;
+                self.lander.liftoff();
+            } else if self.terrain.pads()[pad].refuel {
+                self.lander.fuel = (self.lander.fuel + REFUEL_RATE).min(self.lander.max_fuel);
+                self.attempt.out_of_fuel_frame = None;
+            }
+        }
+
+        if self.state == GameState::Playing && self.attempt.landed_pad.is_none() {
+            if self.stick_rotation != 0.0 {
+                self.lander
+                    .rotate(self.stick_rotation * STICK_ROTATION_RATE);
+            }
+            let key_rotation = held_axis(
+                &self.held_keys,
+                &self.bindings,
+                Action::RotateLeft,
+                Action::RotateRight,
+            );
+            if key_rotation != 0.0 {
+                self.lander.rotate(key_rotation * KEY_ROTATION_RATE);
+            } else if let Some(mouse) = self.mouse_aim() {
+                self.stop_recording("mouse aim");
+                let target = self.camera.to_world(mouse);
+                self.lander.turn_toward(self.lander.angle_toward(target));
+            }
+            if self.autopilot {
+                self.lander.autopilot_step(self.terrain.model());
+            }
+            self.wind.update(self.lander.physics.dt);
+            self.lander.wind = self.wind.force();
+            self.lander.update();
+            self.attempt.elapsed += self.lander.physics.dt;
+
+            self.power_up.update(self.lander.physics.dt);
+            if self.power_up.collect(&mut self.lander) {
+                info!("Thrust boost collected");
+            }
+
+            if self.lander.thrust > 0.0 && self.lander.fuel > 0.0 {
+                let (nozzle, direction) = self.lander.nozzle();
+                self.exhaust.emit(
+                    &mut self.particle_rng,
+                    nozzle,
+                    direction,
+                    self.lander.thrust,
+                );
+            }
+
+            if self.lander.ran_dry() {
+                self.on_fuel_empty();
+            }
+
+            // After liftoff the legs may still graze the pad; only resume
+            // collision checks once clear, or if the lander sinks back down
+            if self.attempt.clearing_surface
+                && (!self.terrain.is_touching(&self.lander) || self.lander.velocity.y < 0.0)
+            {
+                self.attempt.clearing_surface = false;
+            }
+
+            // Check collision with terrain
+            let contact = if self.attempt.clearing_surface {
+                None
+            } else {
+                self.terrain.check_collision(&mut self.lander)
+            };
+            if let Some(contact) = contact {
+                let resting_pad = contact.pad.filter(|&pad| {
+                    let safe = self.lander.is_landed_safely();
+                    if self.terrain.pads()[pad].refuel {
+                        // Refuel stations need a near-stop to dock
+                        safe && self.lander.velocity.length() <= DOCKING_SPEED
+                    } else {
+                        safe && self.touch_and_go
+                    }
+                });
+                if let Some(pad) = resting_pad {
+                    // Score the touchdown before settle() zeroes the velocity
+                    if !self.terrain.pads()[pad].refuel && !self.attempt.visited_pads.contains(&pad)
+                    {
+                        self.attempt.visited_pads.push(pad);
+                        self.award_landing(Some(pad));
+                        self.log_landing(Outcome::PadLanding);
+                        // Level is done once every scoring pad has been visited
+                        let all_visited = self
+                            .terrain
+                            .pads()
+                            .iter()
+                            .enumerate()
+                            .all(|(i, p)| p.refuel || self.attempt.visited_pads.contains(&i));
+                        if all_visited {
+                            self.state = GameState::GameOver;
+                        }
+                    }
+
+                    // Stay on the pad; thrusting lifts off again
+                    self.lander.settle(self.terrain.pad_surface_y(pad));
+                    self.attempt.landed_pad = Some(pad);
+                    debug!("Resting on pad {}", pad);
+                } else if self.lander.is_landed_safely() {
+                    self.state = GameState::GameOver;
+                    let points = self.award_landing(contact.pad);
+                    self.keep_ghost_if_best(points);
+                    self.log_landing(if self.lander.is_pad_landing() {
+                        Outcome::PadLanding
+                    } else {
+                        Outcome::RoughLanding
+                    });
+
+                    // Kick up dust between the legs
+                    let legs = self.lander.get_legs_points();
+                    self.explosions.push(Explosion::new_dust(
+                        &mut self.particle_rng,
+                        (legs[0].x + legs[1].x) / 2.0,
+                        legs[0].y.max(legs[1].y),
+                        self.lander.physics.gravity,
+                    ));
+                } else if can_bounce(&self.lander, contact.part, self.attempt.bounces) {
+                    // A little too fast: bounce and get another chance to settle
+                    self.attempt.bounces += 1;
+                    self.lander.velocity.y = -self.lander.velocity.y * BOUNCE_DAMPING;
+                    self.lander.liftoff();
+                    self.attempt.clearing_surface = true;
+                    debug!("Bounced ({} of {})", self.attempt.bounces, MAX_BOUNCES);
+
+                    let legs = self.lander.get_legs_points();
+                    self.explosions.push(Explosion::new_dust(
+                        &mut self.particle_rng,
+                        (legs[0].x + legs[1].x) / 2.0,
+                        legs[0].y.max(legs[1].y),
+                        self.lander.physics.gravity,
+                    ));
+                } else {
+                    if contact.part == ContactPart::Body {
+                        info!("Hit the terrain body-first");
+                    }
+                    self.crash();
+                }
+            }
+
+            // Hard floor, for anything that slips past the terrain
+            if self.state == GameState::Playing && self.lander.check_floor() {
+                info!("Fell through to the bottom of the screen");
+                self.crash();
+            }
+        }
+
+        self.meteors.update(
+            self.lander.physics.dt,
+            self.terrain.model(),
+            self.lander.physics.gravity,
+        );
+        if self.state == GameState::Playing && self.meteors.hits(&self.lander) {
+            info!("Hit by a meteor");
+            self.lander.crash();
+            self.crash();
+        }
+
+        for explosion in &mut self.explosions {
+            explosion.update();
+        }
+        self.explosions.retain(|explosion| !explosion.is_finished());
+        self.attempt.shake_timer = (self.attempt.shake_timer - self.lander.physics.dt).max(0.0);
+
+        // Replay the crash, then blow up again where it ended
+        if let Some(last) = self.instant_replay.advance() {
+            self.explosions.push(Explosion::new(
+                &mut self.particle_rng,
+                last.x,
+                last.y,
+                self.lander.physics.gravity,
+                self.settings.theme().explosion,
+            ));
+            self.attempt.game_over_time = 0.0;
+        }
+
+        if self.state == GameState::GameOver {
+            self.attempt.game_over_time += self.lander.physics.dt;
+            if self.attempt.game_over_time >= SUMMARY_DELAY && !self.instant_replay.is_playing() {
+                self.state = GameState::Summary;
+                self.summary_row = 0;
+            }
+        }
+        self.exhaust.update();
+
+        let altitude = self.terrain.height_at(self.lander.position.x) - self.lander.position.y;
+        self.camera.update(self.lander.position, altitude);
+    }
+
+    /// Ends the attempt in an explosion, costing a life.
+    fn crash(&mut self) {
+        self.state = GameState::GameOver;
+        self.lives = self.lives.saturating_sub(1);
+        info!("Crashed, {} lives left", self.lives);
+        self.log_landing(Outcome::Crash);
+
+        self.attempt.shake_timer = SHAKE_DURATION;
+        self.shake_magnitude = (self.lander.velocity.length() * SHAKE_PER_SPEED).min(MAX_SHAKE);
+
+        // Harder impacts make bigger explosions
+        let intensity =
+            self.lander.velocity.length() / self.lander.physics.max_safe_landing_velocity;
+        self.explosions.push(Explosion::with_intensity(
+            &mut self.particle_rng,
+            self.lander.position.x,
+            self.lander.position.y,
+            self.lander.physics.gravity,
+            intensity,
+            self.settings.theme().explosion,
+        ));
+    }
+
+    /// Appends this touchdown to the telemetry CSV. Replays are skipped so
+    /// watching one doesn't log the same landing twice.
+    fn log_landing(&self, outcome: Outcome) {
+        if self.replay_player.is_some() {
+            return;
+        }
+        log_outcome(&LandingResult {
+            outcome,
+            velocity: self.lander.velocity.length(),
+            angle_error: self.lander.landing_angle_error(),
+            fuel: self.lander.fuel,
+            level: self.level,
+            score: self.score,
+        });
+    }
+
+    /// The tick the tank runs dry: flashes NO FUEL on the HUD, and `update`
+    /// plays the flameout sound on seeing it.
+    fn on_fuel_empty(&mut self) {
+        info!("Out of fuel");
+        self.attempt.out_of_fuel_frame = Some(self.frame);
+    }
+
+    /// Adds the landing score, scaled by the pad's multiplier, and records a new best.
+    /// Returns the points awarded.
+    fn award_landing(&mut self, pad: Option<usize>) -> u32 {
+        let breakdown = self.compute_landing_score(pad);
+        let points = breakdown.total;
+        self.attempt.last_landing = Some(breakdown);
+        self.score += points;
+        debug!("Landed safely, score: {}", self.score);
+        if self.score > self.high_score {
+            self.high_score = self.score;
+            save_high_score(self.high_score);
+        }
+        if let Some(date) = self.daily {
+            if self.score > self.daily_best {
+                self.daily_best = self.score;
+                save_daily_best(date, self.daily_best);
+            }
+        }
+        points
+    }
+
+    /// The ideal descent fuel as a share of what this attempt burned, 0..1.
+    fn fuel_efficiency(&self) -> f32 {
+        let used = self.lander.fuel_used;
+        if used <= 0.0 {
+            return 1.0;
+        }
+        let height = self.lander.position.y - self.spawn.y;
+        (ideal_descent_fuel(height, &self.lander.physics) / used).min(1.0)
+    }
+
+    /// Replaces the saved ghost when this attempt's landing outscored it.
+    fn keep_ghost_if_best(&mut self, points: u32) {
+        if self
+            .ghost
+            .as_ref()
+            .is_some_and(|ghost| ghost.score() >= points)
+        {
+            self.recorder.clear();
+            return;
+        }
+        info!("New best landing ({}), saving ghost", points);
+        let run = self.recorder.take_run(points);
+        run.save();
+        self.ghost = Some(Ghost::new(run));
+    }
+
+    /// Carries out a console command on the running lander.
+    fn run_console_command(&mut self, command: Command) {
+        // Tweaked physics can't be reproduced from key events alone
+        self.stop_recording("console command");
+        let reply = match command {
+            Command::Set(Tunable::Gravity, gravity) => {
+                self.lander.physics.gravity = gravity;
+                format!("gravity = {} m/s²", gravity)
+            }
+            Command::Set(Tunable::Thrust, thrust) => {
+                self.lander.physics.thrust_power = thrust;
+                format!("thrust = {} m/s²", thrust)
+            }
+            Command::Set(Tunable::Fuel, fuel) => {
+                self.lander.fuel = fuel.min(self.lander.max_fuel);
+                format!("fuel = {}", self.lander.fuel)
+            }
+            Command::Refuel => {
+                self.lander.fuel = self.lander.max_fuel;
+                format!("fuel = {}", self.lander.fuel)
+            }
+            Command::Spawn(_, _) if self.state != GameState::Playing => {
+                "Can only spawn while flying".to_string()
+            }
+            Command::Spawn(x, y) if x > WORLD_WIDTH || y >= self.terrain.height_at(x) => {
+                format!("({}, {}) is not above the terrain", x, y)
+            }
+            Command::Spawn(x, y) => {
+                // Keep any tweaks to the current lander's physics
+                let physics = self.lander.physics;
+                self.spawn = Point2 { x, y };
+                self.lander = self.spawn_lander();
+                self.lander.physics = physics;
+                self.attempt.landed_pad = None;
+                self.attempt.clearing_surface = false;
+                format!("spawned at ({}, {})", x, y)
+            }
+        };
+        info!("Console: {}", reply);
+        self.console.print(reply);
+    }
+
+    /// Unit circle, scaled per star when drawn.
+    fn star_mesh(&self, ctx: &mut Context) -> GameResult<&graphics::Mesh> {
+        if let Some(mesh) = self.star_mesh.get() {
+            return Ok(mesh);
+        }
+        let mesh = graphics::Mesh::new_circle(
+            ctx,
+            graphics::DrawMode::fill(),
+            Point2 { x: 0.0, y: 0.0 },
+            1.0,
+            0.1,
+            Color::WHITE,
+        )?;
+        Ok(self.star_mesh.get_or_init(|| mesh))
+    }
+
+    fn spawn_lander(&self) -> LunarLander {
+        let mut lander = LunarLander::new(self.spawn.x, self.spawn.y, &self.difficulty.settings());
+        if let Some(level) = &self.session_level {
+            lander.physics.gravity = level.gravity;
+            lander.max_fuel = level.fuel;
+        }
+        lander.set_drag(self.drag);
+        lander.set_instant_rotation(self.instant_rotation);
+        lander.set_assist(self.assist);
+        lander.set_thrust_curve(self.thrust_curve);
+        lander.set_edge_mode(self.edge_mode);
+        lander.set_ceiling_mode(self.ceiling_mode);
+        lander.set_trail_length(self.trail_length());
+        lander.fuel = self.level_fuel;
+        lander
+    }
+
+    /// How fast time runs: slowing toward `MIN_TIME_SCALE` as a slow,
+    /// controlled descent nears the ground.
+    fn approach_time_scale(&self) -> f32 {
+        if !self.slow_motion
+            || self.state != GameState::Playing
+            || self.attempt.landed_pad.is_some()
+            || self.lander.velocity.length() > SLOW_MO_SPEED
+        {
+            return 1.0;
+        }
+        let altitude = self.terrain.height_at(self.lander.position.x) - self.lander.position.y;
+        (altitude / SLOW_MO_ALTITUDE).clamp(MIN_TIME_SCALE, 1.0)
+    }
+
+    /// The cursor, while mouse aim is the control scheme and it's the
+    /// player flying rather than a replay.
+    fn mouse_aim(&self) -> Option<Point2<f32>> {
+        if self.settings.control_scheme != ControlScheme::MouseAim || self.replay_player.is_some() {
+            return None;
+        }
+        self.mouse_position
+    }
+
+    fn trail_length(&self) -> usize {
+        if self.show_trail {
+            TRAIL_LENGTH
+        } else {
+            0
+        }
+    }
+
+    /// Handles a key press, live or from a replay.
+    fn press_key(&mut self, ctx: &mut Context, key: KeyCode) -> GameResult {
+        self.held_keys.insert(key);
+        match self.state {
+            GameState::Menu => match key {
+                KeyCode::Return | KeyCode::NumpadEnter => self.start_game(),
+                KeyCode::Left => self.difficulty = self.difficulty.easier(),
+                KeyCode::Right => self.difficulty = self.difficulty.harder(),
+                KeyCode::F9 => self.play_saved_replay(),
+                KeyCode::D => self.start_daily(),
+                KeyCode::E => self.state = GameState::Editor,
+                KeyCode::S => self.state = GameState::Settings,
+                // Any other key leaves the attract demo for a real game
+                _ => self.start_game(),
+            },
+            GameState::Settings => match key {
+                KeyCode::Up => {
+                    self.settings_row = (self.settings_row + SETTINGS_ROWS - 1) % SETTINGS_ROWS
+                }
+                KeyCode::Down => self.settings_row = (self.settings_row + 1) % SETTINGS_ROWS,
+                KeyCode::Left | KeyCode::Right | KeyCode::Return | KeyCode::NumpadEnter => {
+                    let forward = key != KeyCode::Left;
+                    match self.settings_row {
+                        0 => self.settings.vsync = !self.settings.vsync,
+                        1 => {
+                            self.settings.cycle_resolution(forward);
+                            ctx.gfx.set_mode(self.settings.window_mode())?;
+                            let (width, height) = ctx.gfx.drawable_size();
+                            self.letterbox = letterbox(width, height);
+                        }
+                        2 => self.settings.thrust_curve = self.settings.thrust_curve.cycle(forward),
+                        3 => self.settings.theme = self.settings.theme.cycle(forward),
+                        4 => self.settings.random_spawn = !self.settings.random_spawn,
+                        5 => self.settings.high_contrast = !self.settings.high_contrast,
+                        _ => self.settings.control_scheme = self.settings.control_scheme.toggle(),
+                    }
+                    self.settings.save();
+                }
+                KeyCode::Escape | KeyCode::S => self.state = GameState::Menu,
+                _ => (),
+            },
+            GameState::Editor => match key {
+                KeyCode::Escape => self.state = GameState::Menu,
+                KeyCode::S => match config_path(EDITOR_FILE) {
+                    Some(path) => self.editor.save(&path),
+                    None => warn!("No config directory available, level not saved"),
+                },
+                KeyCode::Return | KeyCode::NumpadEnter => {
+                    // Play it as the custom level from now on
+                    if let Some(level) = self.editor.validated() {
+                        self.custom_level = Some(level);
+                        self.start_game();
+                    }
+                }
+                _ => self.editor.press_key(key),
+            },
+            GameState::Playing => {
+                let action = self.bindings.action_for(key);
+                if action == Some(Action::Pause) {
+                    self.toggle_pause();
+                    return Ok(());
+                }
+                if self.paused {
+                    return Ok(());
+                }
+
+                match action {
+                    // Polled from held_keys in step()
+                    Some(Action::Thrust)
+                    | Some(Action::HalfThrust)
+                    | Some(Action::RotateLeft)
+                    | Some(Action::RotateRight) => (),
+                    Some(Action::RcsLeft) | Some(Action::RcsRight) => {
+                        self.lander.apply_rcs(held_axis(
+                            &self.held_keys,
+                            &self.bindings,
+                            Action::RcsLeft,
+                            Action::RcsRight,
+                        ))
+                    }
+                    Some(Action::Restart) => {
+                        debug!("Restarting level...");
+                        self.reset_lander();
+                    }
+                    Some(Action::Pause) => (),
+                    None => match key {
+                        KeyCode::N => self.new_level(),
+                        KeyCode::Return | KeyCode::NumpadEnter if self.settings.show_tutorial => {
+                            self.settings.show_tutorial = false;
+                            self.settings.save();
+                        }
+                        KeyCode::I => {
+                            // Toggle classic instant rotation
+                            self.instant_rotation = !self.instant_rotation;
+                            self.lander.set_instant_rotation(self.instant_rotation);
+                        }
+                        KeyCode::V => self.show_velocity_vector = !self.show_velocity_vector,
+                        KeyCode::X => self.show_landing_reticle = !self.show_landing_reticle,
+                        KeyCode::L => self.show_pad_highlight = !self.show_pad_highlight,
+                        KeyCode::U => {
+                            self.hud_mode = match self.hud_mode {
+                                HudMode::Vector => HudMode::Rates,
+                                HudMode::Rates => HudMode::Vector,
+                            }
+                        }
+                        KeyCode::F => self.show_approach_corridor = !self.show_approach_corridor,
+                        KeyCode::B => {
+                            self.slow_motion = !self.slow_motion;
+                            info!("Slow-motion approach: {}", self.slow_motion);
+                        }
+                        KeyCode::C => {
+                            self.show_trail = !self.show_trail;
+                            self.lander.set_trail_length(self.trail_length());
+                        }
+                        KeyCode::M => self.audio.toggle_mute(ctx)?,
+                        KeyCode::Z => self.camera.toggle_follow(),
+                        KeyCode::Tab => {
+                            self.autopilot = !self.autopilot;
+                            info!("Autopilot: {}", self.autopilot);
+                            if !self.autopilot {
+                                // Hand back with the engines off
+                                self.lander.apply_thrust(0.0);
+                                self.lander.apply_rcs(0.0);
+                            }
+                        }
+                        KeyCode::H => {
+                            self.assist = !self.assist;
+                            self.lander.set_assist(self.assist);
+                            info!("Hover assist: {}", self.assist);
+                        }
+                        KeyCode::G => {
+                            self.touch_and_go = !self.touch_and_go;
+                            info!("Touch-and-go: {}", self.touch_and_go);
+                        }
+                        KeyCode::T => {
+                            self.timed = !self.timed;
+                            info!("Timed mode: {}", self.timed);
+                        }
+                        KeyCode::W => {
+                            // Toggle wrapping at the side walls
+                            self.edge_mode = match self.edge_mode {
+                                EdgeMode::Clamp => EdgeMode::Wrap,
+                                EdgeMode::Wrap => EdgeMode::Clamp,
+                            };
+                            self.lander.set_edge_mode(self.edge_mode);
+                        }
+                        KeyCode::K => {
+                            // Toggle between stopping at the top and a soft pull back
+                            self.ceiling_mode = match self.ceiling_mode {
+                                CeilingMode::Clamp => CeilingMode::Nudge,
+                                CeilingMode::Nudge => CeilingMode::Clamp,
+                            };
+                            self.lander.set_ceiling_mode(self.ceiling_mode);
+                            info!("Ceiling: {:?}", self.ceiling_mode);
+                        }
+                        _ => (),
+                    },
+                }
+            }
+            GameState::GameOver | GameState::Summary => {
+                let options = self.summary_options();
+                let restart = self.bindings.action_for(key) == Some(Action::Restart);
+                match key {
+                    KeyCode::N if self.lives > 0 => self.new_level(),
+                    KeyCode::T if !self.lander.is_landed_safely() => {
+                        // Watch the crash again, with the summary out of the way
+                        self.instant_replay.start();
+                        self.explosions.clear();
+                        self.state = GameState::GameOver;
+                    }
+                    // R skips straight to the first choice
+                    _ if restart => self.choose(options[0]),
+                    _ if self.state == GameState::GameOver => (),
+                    KeyCode::Up | KeyCode::Left => {
+                        self.summary_row = (self.summary_row + options.len() - 1) % options.len()
+                    }
+                    KeyCode::Down | KeyCode::Right => {
+                        self.summary_row = (self.summary_row + 1) % options.len()
+                    }
+                    KeyCode::Return | KeyCode::NumpadEnter => {
+                        self.choose(options[self.summary_row.min(options.len() - 1)])
+                    }
+                    _ => (),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// What the summary offers after this attempt, default first.
+    fn summary_options(&self) -> Vec<SummaryOption> {
+        if self.lander.is_landed_safely() {
+            vec![SummaryOption::Next, SummaryOption::Menu]
+        } else if self.lives > 0 {
+            vec![SummaryOption::Retry, SummaryOption::Menu]
+        } else {
+            vec![SummaryOption::PlayAgain, SummaryOption::Menu]
+        }
+    }
+
+    fn choose(&mut self, option: SummaryOption) {
+        match option {
+            SummaryOption::Next => self.next_level(),
+            SummaryOption::Retry => self.reset_lander(),
+            SummaryOption::PlayAgain if self.daily.is_some() => self.start_daily(),
+            SummaryOption::PlayAgain => self.start_game(),
+            SummaryOption::Menu => self.state = GameState::Menu,
+        }
+    }
+
+    fn release_key(&mut self, key: KeyCode) {
+        self.held_keys.remove(&key);
+        if self.state == GameState::Playing && !self.paused {
+            match self.bindings.action_for(key) {
+                Some(Action::Thrust) | Some(Action::HalfThrust) => {
+                    self.lander.set_thrust_input(0.0)
+                }
+                Some(Action::RcsLeft) | Some(Action::RcsRight) => self.lander.apply_rcs(held_axis(
+                    &self.held_keys,
+                    &self.bindings,
+                    Action::RcsLeft,
+                    Action::RcsRight,
+                )),
+                _ => (),
+            }
+        }
+    }
+
+    /// Feeds in replayed key events that are due before the next tick.
+    fn apply_replay_events(&mut self, ctx: &mut Context) -> GameResult {
+        let Some(player) = &mut self.replay_player else {
+            return Ok(());
+        };
+        let events = player.due(self.session_tick);
+        let finished = player.is_finished();
+        for event in events {
+            if event.pressed {
+                self.press_key(ctx, event.key)?;
+            } else {
+                self.release_key(event.key);
+            }
+        }
+        if finished {
+            info!("Replay finished");
+            self.replay_player = None;
+        }
+        Ok(())
+    }
+
+    /// Drops the session's replay, for input it has no way to reproduce. The
+    /// replay would otherwise drift from what was flown.
+    fn stop_recording(&mut self, cause: &str) {
+        if self.replay_recorder.take().is_some() {
+            info!("Replay recording stopped by {}", cause);
+            self.console
+                .print(format!("Replay recording stopped by {}", cause));
+        }
+    }
+
+    fn save_replay(&self) {
+        let Some(recorder) = &self.replay_recorder else {
+            info!("No replay is being recorded, nothing saved");
+            return;
+        };
+        let Some(path) = config_path(REPLAY_FILE) else {
+            warn!("No config directory available, replay not saved");
+            return;
+        };
+        match recorder.replay().save(&path) {
+            Ok(()) => info!("Saved replay to {}", path.display()),
+            Err(e) => warn!("Failed to save replay to {}: {}", path.display(), e),
+        }
+    }
+
+    /// Loads the saved replay and restarts its session with playback driving input.
+    fn play_saved_replay(&mut self) {
+        let Some(path) = config_path(REPLAY_FILE) else {
+            return;
+        };
+        let replay = match Replay::load(&path) {
+            Ok(replay) => replay,
+            Err(e) => {
+                warn!("Failed to load replay from {}: {}", path.display(), e);
+                return;
+            }
+        };
+        info!("Playing replay from {}", path.display());
+        self.daily = None;
+        self.difficulty = replay.difficulty;
+        self.edge_mode = replay.edge_mode;
+        self.ceiling_mode = replay.ceiling_mode;
+        self.instant_rotation = replay.instant_rotation;
+        self.touch_and_go = replay.touch_and_go;
+        self.timed = replay.timed;
+        self.assist = replay.assist;
+        self.autopilot = replay.autopilot;
+        self.thrust_curve = replay.thrust_curve;
+        self.random_spawn = replay.random_spawn;
+        self.session_level = replay.level.clone();
+        self.start_session(replay.seed);
+        self.replay_player = Some(ReplayPlayer::new(replay));
+    }
+
+    fn draw_hud(&self, canvas: &mut Canvas, ctx: &mut Context) -> GameResult {
+        let theme = self.settings.theme();
+        let fuel_percent = self.lander.fuel_fraction() * 100.0;
+        let fuel_color = if fuel_percent < CRITICAL_FUEL {
+            // Pulse when critically low
+            let alpha = 0.6 + 0.4 * (self.frame as f32 * 0.2).sin();
+            Color::new(1.0, 0.0, 0.0, alpha)
+        } else if fuel_percent < LOW_FUEL {
+            Color::YELLOW
+        } else {
+            theme.hud_text
+        };
+        draw_hud_line(
+            canvas,
+            format!("Fuel: {:.1}%", fuel_percent),
+            10.0,
+            fuel_color,
+        );
+        let velocity = self.lander.velocity;
+        let velocity_text = match self.hud_mode {
+            HudMode::Vector => format!("Velocity: ({:.1}, {:.1})", velocity.x, velocity.y),
+            // Positive drift is to the right
+            HudMode::Rates => format!(
+                "Speed: {:.1} m/s   Drift: {:+.1} m/s",
+                velocity.length(),
+                velocity.x
+            ),
+        };
+        draw_hud_line(canvas, velocity_text, 40.0, theme.hud_text);
+        draw_hud_line(
+            canvas,
+            format!("Angle: {:.1}°", self.lander.angle.to_degrees()),
+            70.0,
+            theme.hud_text,
+        );
+        draw_hud_line(
+            canvas,
+            format!("Spin: {:.1}°/s", self.lander.angular_velocity.to_degrees()),
+            100.0,
+            theme.hud_text,
+        );
+        draw_hud_line(
+            canvas,
+            format!("Gravity: {:.2} m/s²", self.lander.physics.gravity),
+            130.0,
+            theme.hud_text,
+        );
+
+        // Flash the altitude on final approach
+        let altitude = self.terrain.height_at(self.lander.position.x) - self.lander.position.y;
+        let flash_on = (self.frame / 8).is_multiple_of(2);
+        let altitude_color = if altitude < LOW_ALTITUDE && !flash_on {
+            Color::new(1.0, 1.0, 1.0, 0.2)
+        } else {
+            theme.hud_text
+        };
+        draw_hud_line(
+            canvas,
+            format!("Altitude: {:.0} m", altitude),
+            160.0,
+            altitude_color,
+        );
+
+        // World y is up, so descending means negative velocity.y
+        let descent_rate = -self.lander.velocity.y;
+        let descent_color = if descent_rate > self.lander.physics.max_safe_landing_velocity {
+            Color::RED
+        } else {
+            theme.hud_text
+        };
+        draw_hud_line(
+            canvas,
+            format!("Descent: {:.1} m/s", descent_rate),
+            190.0,
+            descent_color,
+        );
+
+        self.draw_fuel_gauge(canvas, ctx, 222.0)?;
+        self.draw_drift_gauge(canvas, ctx)?;
+
+        // Counts down in timed mode, up otherwise
+        let (time, time_color) = if self.timed {
+            let remaining = self.time_remaining();
+            let color = if remaining < 10.0 {
+                Color::YELLOW
+            } else {
+                theme.hud_text
+            };
+            (remaining, color)
+        } else {
+            (self.attempt.elapsed, theme.hud_text)
+        };
+        draw_hud_line(canvas, format!("Time: {:.1}", time), 245.0, time_color);
+        draw_hud_line(
+            canvas,
+            format!(
+                "Level: {}   Lives: {}   {}",
+                self.level,
+                self.lives,
+                self.difficulty.name()
+            ),
+            275.0,
+            theme.hud_text,
+        );
+
+        // Seed in the corner so a bad level can be reported and reproduced
+        let seed_text =
+            Text::new(TextFragment::new(format!("Seed: {}", self.seed)).scale(PxScale::from(14.0)));
+        canvas.draw(
+            &seed_text,
+            graphics::DrawParam::default()
+                .dest([10.0, SCREEN_HEIGHT - 10.0])
+                .offset([0.0, 1.0])
+                .color(Color::new(1.0, 1.0, 1.0, 0.5)),
+        );
+
+        if let Some(frame) = self.attempt.out_of_fuel_frame {
+            let flash_on = (self.frame / 8).is_multiple_of(2);
+            if self.frame - frame < OUT_OF_FUEL_BANNER_FRAMES
+                && self.state == GameState::Playing
+                && flash_on
+            {
+                let banner = Text::new(TextFragment::new("NO FUEL").scale(PxScale::from(32.0)));
+                canvas.draw(
+                    &banner,
+                    graphics::DrawParam::default()
+                        .dest([SCREEN_WIDTH / 2.0, 200.0])
+                        .offset([0.5, 0.5])
+                        .color(Color::RED),
+                );
+            }
+        }
+
+        let docked = self
+            .attempt
+            .landed_pad
+            .is_some_and(|pad| self.terrain.pads()[pad].refuel);
+        if docked && self.state == GameState::Playing {
+            let banner = Text::new(
+                TextFragment::new("REFUELING - thrust to lift off").scale(PxScale::from(24.0)),
+            );
+            canvas.draw(
+                &banner,
+                graphics::DrawParam::default()
+                    .dest([SCREEN_WIDTH / 2.0, 200.0])
+                    .offset([0.5, 0.5])
+                    .color(Color::CYAN),
+            );
+        }
+
+        if self.lander.drag > 0.0 {
+            let atm_text = Text::new(TextFragment::new("ATM").scale(PxScale::from(20.0)));
+            canvas.draw(
+                &atm_text,
+                graphics::DrawParam::default()
+                    .dest([SCREEN_WIDTH - 10.0, 10.0])
+                    .offset([1.0, 0.0])
+                    .color(Color::CYAN),
+            );
+        }
+
+        if self.edge_mode == EdgeMode::Wrap {
+            let wrap_text = Text::new(TextFragment::new("WRAP").scale(PxScale::from(20.0)));
+            canvas.draw(
+                &wrap_text,
+                graphics::DrawParam::default()
+                    .dest([SCREEN_WIDTH - 10.0, 35.0])
+                    .offset([1.0, 0.0])
+                    .color(Color::CYAN),
+            );
+        }
+
+        if self.autopilot && self.state == GameState::Playing {
+            let auto_text = Text::new(TextFragment::new("AUTO").scale(PxScale::from(20.0)));
+            canvas.draw(
+                &auto_text,
+                graphics::DrawParam::default()
+                    .dest([SCREEN_WIDTH - 10.0, 85.0])
+                    .offset([1.0, 0.0])
+                    .color(Color::GREEN),
+            );
+        }
+
+        if self.assist {
+            let assist_text = Text::new(TextFragment::new("ASSIST").scale(PxScale::from(20.0)));
+            canvas.draw(
+                &assist_text,
+                graphics::DrawParam::default()
+                    .dest([SCREEN_WIDTH - 10.0, 110.0])
+                    .offset([1.0, 0.0])
+                    .color(Color::CYAN),
+            );
+        }
+
+        if !self.wind.is_calm() {
+            // Chevrons point downwind, more of them for a stronger push
+            let force = self.wind.force();
+            let chevron = if force < 0.0 { "<" } else { ">" };
+            let chevrons = chevron.repeat((force.abs() * 3.0).ceil().clamp(1.0, 5.0) as usize);
+            let wind_text = Text::new(
+                TextFragment::new(format!("WIND {} {:.1}", chevrons, force.abs()))
+                    .scale(PxScale::from(20.0)),
+            );
+            canvas.draw(
+                &wind_text,
+                graphics::DrawParam::default()
+                    .dest([SCREEN_WIDTH - 10.0, 135.0])
+                    .offset([1.0, 0.0])
+                    .color(Color::new(0.8, 0.9, 1.0, 0.8)),
+            );
+        }
+
+        let boost = self.lander.boost_left();
+        if boost > 0.0 {
+            // Countdown bar under the label, draining as the boost runs out
+            let boost_text = Text::new(TextFragment::new("BOOST").scale(PxScale::from(20.0)));
+            canvas.draw(
+                &boost_text,
+                graphics::DrawParam::default()
+                    .dest([SCREEN_WIDTH - 10.0, 160.0])
+                    .offset([1.0, 0.0])
+                    .color(Color::MAGENTA),
+            );
+            let width = FUEL_BAR_WIDTH * (boost / BOOST_DURATION).min(1.0);
+            let bar = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(
+                    SCREEN_WIDTH - 10.0 - width,
+                    182.0,
+                    width,
+                    FUEL_BAR_HEIGHT / 2.0,
+                ),
+                Color::MAGENTA,
+            )?;
+            canvas.draw(&bar, graphics::DrawParam::default());
+        }
+
+        if self.time_scale < 1.0 {
+            let slow_text = Text::new(TextFragment::new("SLOW-MO").scale(PxScale::from(20.0)));
+            canvas.draw(
+                &slow_text,
+                graphics::DrawParam::default()
+                    .dest([SCREEN_WIDTH - 10.0, 200.0])
+                    .offset([1.0, 0.0])
+                    .color(Color::new(0.6, 0.8, 1.0, 0.8)),
+            );
+        }
+
+        if self.replay_player.is_some() {
+            let replay_text = Text::new(TextFragment::new("REPLAY").scale(PxScale::from(20.0)));
+            canvas.draw(
+                &replay_text,
+                graphics::DrawParam::default()
+                    .dest([SCREEN_WIDTH - 10.0, 60.0])
+                    .offset([1.0, 0.0])
+                    .color(Color::YELLOW),
+            );
+        }
+
+        // The outcome shows over the playfield until the summary comes up
+        if self.state == GameState::GameOver && !self.instant_replay.is_playing() {
+            let (headline, color) = self.outcome_headline(&theme);
+            let text = Text::new(TextFragment::new(headline).scale(PxScale::from(40.0)));
+            canvas.draw(
+                &text,
+                graphics::DrawParam::default()
+                    .dest([SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0])
+                    .offset([0.5, 0.5]) // Center the text
+                    .color(color),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// The attempt's outcome and the color it's shown in, with a symbol as
+    /// well when the theme asks for shape cues.
+    fn outcome_headline(&self, theme: &Theme) -> (String, Color) {
+        let (headline, color) = if self.lander.is_pad_landing() {
+            ("Perfect Pad Landing!", Color::GREEN)
+        } else if self.lander.is_landed_safely() {
+            ("Rough Landing", Color::YELLOW)
+        } else if self.lives > 0 {
+            ("Crash Landing!", Color::RED)
+        } else {
+            ("Game Over", Color::RED)
+        };
+        // √ and × rather than ✓ and ✗, which the default font lacks
+        let headline = match (theme.shape_cues, self.lander.is_landed_safely()) {
+            (false, _) => headline.to_string(),
+            (true, true) => format!("√ {}", headline),
+            (true, false) => format!("× {}", headline),
+        };
+        (headline, color)
+    }
+
+    /// End-of-attempt panel: outcome, score breakdown, and what to do next.
+    fn draw_summary(&self, canvas: &mut Canvas, ctx: &mut Context) -> GameResult {
+        let theme = self.settings.theme();
+        let [x, y, w, h] = SUMMARY_PANEL;
+        let panel = graphics::Rect::new(x, y, w, h);
+        let background = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            panel,
+            Color {
+                a: 0.92,
+                ..theme.background
+            },
+        )?;
+        canvas.draw(&background, graphics::DrawParam::default());
+        let border = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::stroke(2.0),
+            panel,
+            theme.hud_text,
+        )?;
+        canvas.draw(&border, graphics::DrawParam::default());
+
+        let center_x = x + w / 2.0;
+        let (headline, color) = self.outcome_headline(&theme);
+        let text = Text::new(TextFragment::new(headline).scale(PxScale::from(32.0)));
+        canvas.draw(
+            &text,
+            graphics::DrawParam::default()
+                .dest([center_x, y + 35.0])
+                .offset([0.5, 0.5])
+                .color(color),
+        );
+        if let Some(date) = self.daily {
+            let text = Text::new(
+                TextFragment::new(format!("Daily challenge {}", date)).scale(PxScale::from(16.0)),
+            );
+            canvas.draw(
+                &text,
+                graphics::DrawParam::default()
+                    .dest([center_x, y + 62.0])
+                    .offset([0.5, 0.5])
+                    .color(theme.hud_text),
+            );
+        }
+
+        let mut lines = Vec::new();
+        match self.attempt.last_landing {
+            Some(breakdown) if self.lander.is_landed_safely() => {
+                lines.push(format!("Fuel bonus: {:.0}", breakdown.fuel));
+                lines.push(format!("Velocity bonus: {:.0}", breakdown.velocity));
+                lines.push(format!("Attitude bonus: {:.0}", breakdown.attitude));
+                if self.timed {
+                    lines.push(format!("Time bonus: {:.0}", breakdown.time));
+                }
+                if breakdown.factor < 1.0 {
+                    lines.push(format!("Off the pad: x{}", breakdown.factor));
+                } else {
+                    lines.push(format!("Pad multiplier: x{}", breakdown.multiplier));
+                }
+                lines.push(format!("Landing score: {}", breakdown.total));
+                let efficiency = self.fuel_efficiency();
+                lines.push(format!(
+                    "Fuel efficiency: {} ({:.0}%)",
+                    efficiency_grade(efficiency),
+                    efficiency * 100.0
+                ));
+            }
+            _ => {
+                lines.push(format!("Lives left: {}", self.lives));
+                lines.push("T: instant replay".to_string());
+            }
+        }
+        lines.push(if self.daily.is_some() {
+            format!("Score: {}   Daily best: {}", self.score, self.daily_best)
+        } else {
+            format!("Score: {}   Best: {}", self.score, self.high_score)
+        });
+        for (i, line) in lines.into_iter().enumerate() {
+            let text = Text::new(TextFragment::new(line).scale(PxScale::from(18.0)));
+            canvas.draw(
+                &text,
+                graphics::DrawParam::default()
+                    .dest([center_x, y + 80.0 + i as f32 * 24.0])
+                    .offset([0.5, 0.5])
+                    .color(theme.hud_text),
+            );
+        }
+
+        // Options side by side along the bottom, the selected one bracketed
+        let options = self.summary_options();
+        let spacing = w / options.len() as f32;
+        for (i, option) in options.into_iter().enumerate() {
+            let selected = i == self.summary_row;
+            let label = if selected {
+                format!("[ {} ]", option.label())
+            } else {
+                option.label().to_string()
+            };
+            let text = Text::new(TextFragment::new(label).scale(PxScale::from(20.0)));
+            canvas.draw(
+                &text,
+                graphics::DrawParam::default()
+                    .dest([x + spacing * (i as f32 + 0.5), y + h - 30.0])
+                    .offset([0.5, 0.5])
+                    .color(if selected {
+                        Color::YELLOW
+                    } else {
+                        theme.hud_text
+                    }),
+            );
+        }
+        Ok(())
+    }
+
+    fn draw_fuel_gauge(&self, canvas: &mut Canvas, ctx: &mut Context, y: f32) -> GameResult {
+        let fraction = self.lander.fuel_fraction();
+
+        // Fill shifts from green when full to red when empty
+        if fraction > 0.0 {
+            let fill = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                graphics::Rect::new(10.0, y, FUEL_BAR_WIDTH * fraction, FUEL_BAR_HEIGHT),
+                Color::new(1.0 - fraction, fraction, 0.0, 1.0),
+            )?;
+            canvas.draw(&fill, graphics::DrawParam::default());
+        }
+
+        let outline = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::stroke(1.0),
+            graphics::Rect::new(10.0, y, FUEL_BAR_WIDTH, FUEL_BAR_HEIGHT),
+            Color::WHITE,
+        )?;
+        canvas.draw(&outline, graphics::DrawParam::default());
+
+        Ok(())
+    }
+
+    /// Horizontal speed as a needle on a bar centered on zero, with the
+    /// band slow enough to land in marked. Green inside the band.
+    fn draw_drift_gauge(&self, canvas: &mut Canvas, ctx: &mut Context) -> GameResult {
+        let center = SCREEN_WIDTH / 2.0;
+        let scale = DRIFT_GAUGE_WIDTH / 2.0 / DRIFT_GAUGE_RANGE; // px per m/s
+        let safe = self.lander.physics.max_safe_landing_velocity;
+        let drift = self.lander.velocity.x;
+
+        let band = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(
+                center - safe * scale,
+                DRIFT_GAUGE_TOP,
+                2.0 * safe * scale,
+                DRIFT_GAUGE_HEIGHT,
+            ),
+            Color::new(0.0, 1.0, 0.0, 0.25),
+        )?;
+        canvas.draw(&band, graphics::DrawParam::default());
+
+        let outline = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::stroke(1.0),
+            graphics::Rect::new(
+                center - DRIFT_GAUGE_WIDTH / 2.0,
+                DRIFT_GAUGE_TOP,
+                DRIFT_GAUGE_WIDTH,
+                DRIFT_GAUGE_HEIGHT,
+            ),
+            Color::WHITE,
+        )?;
+        canvas.draw(&outline, graphics::DrawParam::default());
+
+        let x = center + drift.clamp(-DRIFT_GAUGE_RANGE, DRIFT_GAUGE_RANGE) * scale;
+        let needle_color = if drift.abs() <= safe {
+            Color::GREEN
+        } else if drift.abs() <= 2.0 * safe {
+            Color::YELLOW
+        } else {
+            Color::RED
+        };
+        let needle = graphics::Mesh::new_line(
+            ctx,
+            &[
+                Point2 {
+                    x,
+                    y: DRIFT_GAUGE_TOP - 4.0,
+                },
+                Point2 {
+                    x,
+                    y: DRIFT_GAUGE_TOP + DRIFT_GAUGE_HEIGHT + 4.0,
+                },
+            ],
+            3.0,
+            needle_color,
+        )?;
+        canvas.draw(&needle, graphics::DrawParam::default());
+
+        let label = Text::new(TextFragment::new("DRIFT").scale(PxScale::from(16.0)));
+        canvas.draw(
+            &label,
+            graphics::DrawParam::default()
+                .dest([
+                    center - DRIFT_GAUGE_WIDTH / 2.0 - 8.0,
+                    DRIFT_GAUGE_TOP - 2.0,
+                ])
+                .offset([1.0, 0.0])
+                .color(self.settings.theme().hud_text),
+        );
+        Ok(())
+    }
+
+    /// An arrow at the top edge under the lander, with its height above
+    /// the view, while it's flown out of sight.
+    fn draw_offscreen_indicator(&self, canvas: &mut Canvas, ctx: &mut Context) -> GameResult {
+        let view = self.camera.view();
+        let above = view.y - self.lander.position.y;
+        if above <= 0.0 {
+            return Ok(());
+        }
+        let x = ((self.lander.position.x - view.x) * SCREEN_WIDTH / view.w)
+            .clamp(10.0, SCREEN_WIDTH - 10.0);
+        let color = self.settings.theme().hud_text;
+        draw_arrow(ctx, canvas, Vec2::new(x, 45.0), Vec2::new(x, 10.0), color)?;
+        let text = Text::new(TextFragment::new(format!("{:.0}", above)).scale(PxScale::from(16.0)));
+        canvas.draw(
+            &text,
+            graphics::DrawParam::default()
+                .dest([x, 50.0])
+                .offset([0.5, 0.0])
+                .color(color),
+        );
+        Ok(())
     }
 
-    fn draw_hud(&self, canvas: &mut Canvas, _ctx: &mut Context) -> GameResult {
-        let fuel_text = Text::new(
-            TextFragment::new(format!("Fuel: {:.1}%", self.lander.fuel)).scale(PxScale::from(20.0)),
-        );
-        let velocity_text = Text::new(
-            TextFragment::new(format!(
-                "Velocity: ({:.1}, {:.1})",
-                self.lander.velocity.x, self.lander.velocity.y
-            ))
-            .scale(PxScale::from(20.0)),
-        );
-        let angle_text = Text::new(
-            TextFragment::new(format!("Angle: {:.1}°", self.lander.angle.to_degrees()))
-                .scale(PxScale::from(20.0)),
+    /// The whole world in miniature: terrain profile, pads, and the lander.
+    fn draw_minimap(&self, canvas: &mut Canvas, ctx: &mut Context) -> GameResult {
+        let to_map = |x: f32, y: f32| Point2 {
+            x: MINIMAP_ORIGIN[0] + x * MINIMAP_SCALE,
+            y: MINIMAP_ORIGIN[1] + y * MINIMAP_SCALE,
+        };
+        let bounds = graphics::Rect::new(
+            MINIMAP_ORIGIN[0],
+            MINIMAP_ORIGIN[1],
+            WORLD_WIDTH * MINIMAP_SCALE,
+            SCREEN_HEIGHT * MINIMAP_SCALE,
         );
 
+        let mut mb = graphics::MeshBuilder::new();
+        mb.rectangle(
+            graphics::DrawMode::fill(),
+            bounds,
+            Color::new(0.0, 0.0, 0.0, 0.5),
+        )?;
+        mb.rectangle(
+            graphics::DrawMode::stroke(1.0),
+            bounds,
+            Color::new(1.0, 1.0, 1.0, 0.5),
+        )?;
+        let profile: Vec<Point2<f32>> = self
+            .terrain
+            .surface_points()
+            .into_iter()
+            .map(|p| to_map(p.x, p.y))
+            .collect();
+        mb.line(&profile, 1.0, Color::from_rgb(150, 150, 150))?;
+        for (i, pad) in self.terrain.pads().iter().enumerate() {
+            let color = if pad.refuel {
+                Color::CYAN
+            } else {
+                Color::GREEN
+            };
+            let center = to_map(self.terrain.pad_center_x(i), self.terrain.pad_surface_y(i));
+            mb.circle(graphics::DrawMode::fill(), center, 2.0, 0.1, color)?;
+        }
+        let lander = to_map(self.lander.position.x, self.lander.position.y);
+        mb.circle(graphics::DrawMode::fill(), lander, 2.0, 0.1, Color::WHITE)?;
+
         canvas.draw(
-            &fuel_text,
-            graphics::DrawParam::default()
-                .dest([10.0, 10.0])
-                .color(Color::WHITE),
+            &graphics::Mesh::from_data(ctx, mb.build()),
+            graphics::DrawParam::default(),
         );
+        Ok(())
+    }
+
+    fn draw_menu(&self, canvas: &mut Canvas) {
+        let lines = [
+            ("LUNAR LANDER", 60.0, 200.0),
+            ("Press any key to start", 24.0, 300.0),
+            (
+                &*format!("Difficulty: < {} >", self.difficulty.name()),
+                20.0,
+                340.0,
+            ),
+            (&*format!("Best: {}", self.high_score), 20.0, 380.0),
+            ("D: today's daily challenge", 16.0, 420.0),
+            ("F9: watch saved replay", 16.0, 445.0),
+            ("S: settings", 16.0, 470.0),
+            ("E: level editor", 16.0, 495.0),
+        ];
+        for (text, size, y) in lines {
+            let text = Text::new(TextFragment::new(text).scale(PxScale::from(size)));
+            canvas.draw(
+                &text,
+                graphics::DrawParam::default()
+                    .dest([SCREEN_WIDTH / 2.0, y])
+                    .offset([0.5, 0.5])
+                    .color(Color::WHITE),
+            );
+        }
+    }
+
+    /// First-run help: the goal, the controls from the current bindings, and
+    /// arrows to the gauges that matter most. Drawn over play without
+    /// pausing it.
+    fn draw_tutorial(&self, canvas: &mut Canvas, ctx: &mut Context) -> GameResult {
+        let keys = |action| key_names(&self.bindings, action);
+        let lines = [
+            "Land gently and upright on a pad".to_string(),
+            format!(
+                "{}: thrust   {}: half thrust",
+                keys(Action::Thrust),
+                keys(Action::HalfThrust)
+            ),
+            format!(
+                "{} / {}: rotate",
+                keys(Action::RotateLeft),
+                keys(Action::RotateRight)
+            ),
+            format!(
+                "{} / {}: side thrusters",
+                keys(Action::RcsLeft),
+                keys(Action::RcsRight)
+            ),
+            format!(
+                "{}: restart   N: new terrain   {}: pause",
+                keys(Action::Restart),
+                keys(Action::Pause)
+            ),
+            "Enter: hide this help".to_string(),
+        ];
+        let color = Color::new(0.6, 1.0, 0.6, 0.9);
+        for (i, line) in lines.into_iter().enumerate() {
+            let text = Text::new(TextFragment::new(line).scale(PxScale::from(18.0)));
+            canvas.draw(
+                &text,
+                graphics::DrawParam::default()
+                    .dest([SCREEN_WIDTH - 10.0, TUTORIAL_TOP + i as f32 * 24.0])
+                    .offset([1.0, 0.0])
+                    .color(color),
+            );
+        }
+
+        // Point out the gauges that decide a landing
+        let callouts = [
+            ("Keep this below the safe speed", 200.0),
+            ("Fuel left", 228.0),
+        ];
+        for (label, y) in callouts {
+            draw_arrow(ctx, canvas, Vec2::new(300.0, y), Vec2::new(240.0, y), color)?;
+            let text = Text::new(TextFragment::new(label).scale(PxScale::from(16.0)));
+            canvas.draw(
+                &text,
+                graphics::DrawParam::default()
+                    .dest([308.0, y])
+                    .offset([0.0, 0.5])
+                    .color(color),
+            );
+        }
+        Ok(())
+    }
+
+    fn draw_settings(&self, canvas: &mut Canvas) {
+        let (width, height) = self.settings.dimensions();
+        let rows = [
+            format!(
+                "VSync: < {} > (applies on restart)",
+                if self.settings.vsync { "On" } else { "Off" }
+            ),
+            format!("Resolution: < {}x{} >", width, height),
+            format!("Thrust curve: < {} >", self.settings.thrust_curve.name()),
+            format!("Theme: < {} >", self.settings.theme.name()),
+            format!(
+                "Random spawn: < {} >",
+                if self.settings.random_spawn {
+                    "On"
+                } else {
+                    "Off"
+                }
+            ),
+            format!(
+                "High contrast: < {} >",
+                if self.settings.high_contrast {
+                    "On"
+                } else {
+                    "Off"
+                }
+            ),
+            format!("Controls: < {} >", self.settings.control_scheme.name()),
+        ];
+        let title = Text::new(TextFragment::new("SETTINGS").scale(PxScale::from(40.0)));
         canvas.draw(
-            &velocity_text,
+            &title,
             graphics::DrawParam::default()
-                .dest([10.0, 40.0])
+                .dest([SCREEN_WIDTH / 2.0, 200.0])
+                .offset([0.5, 0.5])
                 .color(Color::WHITE),
         );
+        for (i, row) in rows.into_iter().enumerate() {
+            let color = if i == self.settings_row {
+                Color::YELLOW
+            } else {
+                Color::WHITE
+            };
+            let text = Text::new(TextFragment::new(row).scale(PxScale::from(20.0)));
+            canvas.draw(
+                &text,
+                graphics::DrawParam::default()
+                    .dest([SCREEN_WIDTH / 2.0, 300.0 + i as f32 * 35.0])
+                    .offset([0.5, 0.5])
+                    .color(color),
+            );
+        }
+        let hint = Text::new(
+            TextFragment::new("Up/Down: select   Left/Right: change   Esc: back")
+                .scale(PxScale::from(16.0)),
+        );
         canvas.draw(
-            &angle_text,
+            &hint,
             graphics::DrawParam::default()
-                .dest([10.0, 70.0])
+                .dest([SCREEN_WIDTH / 2.0, 320.0 + SETTINGS_ROWS as f32 * 35.0])
+                .offset([0.5, 0.5])
                 .color(Color::WHITE),
         );
+    }
 
-        if self.game_over {
-            let game_over_text = if self.lander.is_landed_safely() {
-                "Successful Landing!"
-            } else {
-                "Crash Landing!"
-            };
-            let text = Text::new(TextFragment::new(game_over_text).scale(PxScale::from(40.0)));
-            let screen_center = Point2 { x: 400.0, y: 300.0 };
+    /// Frame timing, particle load, and the raw lander state, for profiling.
+    fn draw_debug_overlay(&self, canvas: &mut Canvas, ctx: &mut Context) {
+        let particles = self.exhaust.particle_count()
+            + self
+                .explosions
+                .iter()
+                .map(Explosion::particle_count)
+                .sum::<usize>();
+        let lander = &self.lander;
+        let lines = [
+            format!("FPS: {:.0}", ctx.time.fps()),
+            format!("Ticks this frame: {}", self.ticks_last_frame),
+            format!("Particles: {}", particles),
+            format!(
+                "Position: ({:.2}, {:.2})",
+                lander.position.x, lander.position.y
+            ),
+            format!(
+                "Velocity: ({:.3}, {:.3})",
+                lander.velocity.x, lander.velocity.y
+            ),
+            format!(
+                "Angle: {:.4} rad  Spin: {:.4} rad/s",
+                lander.angle, lander.angular_velocity
+            ),
+            format!(
+                "Thrust: {:.2}  RCS: {:.2}  Fuel: {:.2}",
+                lander.thrust, lander.rcs, lander.fuel
+            ),
+            format!(
+                "Landing checked: {}  Safe: {}",
+                lander.is_landing_checked(),
+                lander.is_landed_safely()
+            ),
+            format!("State: {:?}  Tick: {}", self.state, self.session_tick),
+        ];
+        for (i, line) in lines.into_iter().enumerate() {
+            let text = Text::new(TextFragment::new(line).scale(PxScale::from(14.0)));
             canvas.draw(
                 &text,
                 graphics::DrawParam::default()
-                    .dest(screen_center)
-                    .offset([0.5, 0.5]) // Center the text
-                    .color(if self.lander.is_landed_safely() {
-                        Color::GREEN
-                    } else {
-                        Color::RED
-                    }),
+                    .dest([10.0, DEBUG_OVERLAY_TOP + i as f32 * 16.0])
+                    .color(Color::from_rgb(255, 200, 0)),
             );
+        }
+    }
 
-            let restart_text =
-                Text::new(TextFragment::new("Press R to restart").scale(PxScale::from(20.0)));
+    /// Blacks out the window outside the logical screen.
+    fn draw_letterbox(&self, canvas: &mut Canvas, ctx: &mut Context) -> GameResult {
+        let outer = self.letterbox;
+        let bars = [
+            graphics::Rect::new(outer.x, outer.y, -outer.x, outer.h),
+            graphics::Rect::new(SCREEN_WIDTH, outer.y, -outer.x, outer.h),
+            graphics::Rect::new(outer.x, outer.y, outer.w, -outer.y),
+            graphics::Rect::new(outer.x, SCREEN_HEIGHT, outer.w, -outer.y),
+        ];
+        let mut mb = graphics::MeshBuilder::new();
+        let mut any = false;
+        for bar in bars.into_iter().filter(|bar| bar.w > 0.0 && bar.h > 0.0) {
+            mb.rectangle(graphics::DrawMode::fill(), bar, Color::BLACK)?;
+            any = true;
+        }
+        if any {
             canvas.draw(
-                &restart_text,
-                graphics::DrawParam::default()
-                    .dest([400.0, 350.0])
-                    .offset([0.5, 0.5])
-                    .color(Color::WHITE),
+                &graphics::Mesh::from_data(ctx, mb.build()),
+                graphics::DrawParam::default(),
             );
         }
+        Ok(())
+    }
+
+    fn draw_pause_overlay(&self, canvas: &mut Canvas, ctx: &mut Context) -> GameResult {
+        // Dim the frozen frame
+        let dim = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, SCREEN_WIDTH, SCREEN_HEIGHT),
+            Color::new(0.0, 0.0, 0.0, 0.5),
+        )?;
+        canvas.draw(&dim, graphics::DrawParam::default());
+
+        let text = Text::new(TextFragment::new("PAUSED").scale(PxScale::from(40.0)));
+        canvas.draw(
+            &text,
+            graphics::DrawParam::default()
+                .dest([SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0])
+                .offset([0.5, 0.5])
+                .color(Color::WHITE),
+        );
 
         Ok(())
     }
 }
 
-fn generate_stars() -> Vec<Point2<f32>> {
-    let mut rng = rand::thread_rng();
+fn draw_hud_line(canvas: &mut Canvas, text: String, y: f32, color: Color) {
+    let text = Text::new(TextFragment::new(text).scale(PxScale::from(20.0)));
+    canvas.draw(
+        &text,
+        graphics::DrawParam::default().dest([10.0, y]).color(color),
+    );
+}
+
+/// Scores a judged touchdown: up to 1000 for remaining fuel, 1000 for a
+/// gentle descent and 500 for being level with the surface, plus
+/// `time_score`, times `multiplier`. Rough landings off a pad keep only a
+/// small share, and crashes score 0.
+fn landing_score(lander: &LunarLander, time_score: f32, multiplier: u32) -> ScoreBreakdown {
+    if !lander.is_landed_safely() {
+        return ScoreBreakdown::default();
+    }
+
+    let fuel_score = lander.fuel_fraction() * 1000.0;
+    let speed = lander.velocity.length();
+    let velocity_score = (1.0 - speed / lander.physics.max_safe_landing_velocity).max(0.0) * 1000.0;
+    let angle_error = lander.landing_angle_error();
+    let level_score = (1.0 - angle_error / lander.physics.max_safe_landing_angle).max(0.0) * 500.0;
+
+    let factor = if lander.is_pad_landing() {
+        1.0
+    } else {
+        ROUGH_LANDING_FACTOR
+    };
+    let score = (fuel_score + velocity_score + level_score + time_score) * factor;
+    ScoreBreakdown {
+        fuel: fuel_score,
+        velocity: velocity_score,
+        attitude: level_score,
+        time: time_score,
+        factor,
+        multiplier,
+        total: score.round() as u32 * multiplier,
+    }
+}
+
+/// Whether an unsafe touchdown was only marginally too fast: legs first,
+/// level enough, coming down, and within the bounce band above the safe
+/// speed. Runs out after `MAX_BOUNCES`.
+fn can_bounce(lander: &LunarLander, part: ContactPart, bounces: u32) -> bool {
+    let physics = &lander.physics;
+    let speed = lander.velocity.length();
+    part == ContactPart::Legs
+        && bounces < MAX_BOUNCES
+        && lander.velocity.y < 0.0
+        && lander.landing_angle_error() <= physics.max_safe_landing_angle
+        && speed <= physics.max_safe_landing_velocity + physics.bounce_velocity_margin
+}
+
+/// Letter grade for a `fuel_efficiency` share.
+fn efficiency_grade(efficiency: f32) -> char {
+    match efficiency {
+        e if e >= 0.8 => 'A',
+        e if e >= 0.6 => 'B',
+        e if e >= 0.4 => 'C',
+        e if e >= 0.2 => 'D',
+        _ => 'F',
+    }
+}
+
+/// The keys bound to `action`, for display.
+fn key_names(bindings: &KeyBindings, action: Action) -> String {
+    bindings
+        .keys(action)
+        .iter()
+        .map(|key| format!("{:?}", key))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn is_held(held_keys: &HashSet<KeyCode>, bindings: &KeyBindings, action: Action) -> bool {
+    bindings
+        .keys(action)
+        .iter()
+        .any(|key| held_keys.contains(key))
+}
+
+/// -1, 0 or 1 from a pair of opposing actions; holding both cancels out.
+fn held_axis(
+    held_keys: &HashSet<KeyCode>,
+    bindings: &KeyBindings,
+    negative: Action,
+    positive: Action,
+) -> f32 {
+    let held = |action| is_held(held_keys, bindings, action);
+    (held(positive) as i32 - held(negative) as i32) as f32
+}
+
+/// Throttle from the held thrust keys; full thrust wins over half.
+fn held_thrust(held_keys: &HashSet<KeyCode>, bindings: &KeyBindings) -> f32 {
+    if is_held(held_keys, bindings, Action::Thrust) {
+        1.0
+    } else if is_held(held_keys, bindings, Action::HalfThrust) {
+        0.5
+    } else {
+        0.0
+    }
+}
+
+/// A background star on one of the `STAR_LAYERS`.
+struct Star {
+    position: Point2<f32>,
+    layer: usize,
+    twinkle_phase: f32,
+    twinkle_rate: f32, // radians per tick
+}
+
+impl Star {
+    /// Layer brightness modulated by a slow sine twinkle.
+    fn alpha(&self, frame: u64) -> f32 {
+        let (_, _, brightness) = STAR_LAYERS[self.layer];
+        let twinkle = (frame as f32 * self.twinkle_rate + self.twinkle_phase).sin();
+        brightness * (0.75 + 0.25 * twinkle)
+    }
+}
+
+fn generate_stars(seed: u64) -> Vec<Star> {
+    let mut rng = StdRng::seed_from_u64(seed);
     let mut stars = Vec::new();
     for _ in 0..100 {
-        stars.push(Point2 {
-            x: rng.gen_range(0.0..800.0),
-            y: rng.gen_range(0.0..600.0),
+        stars.push(Star {
+            position: Point2 {
+                x: rng.gen_range(0.0..SCREEN_WIDTH),
+                y: rng.gen_range(0.0..SCREEN_HEIGHT),
+            },
+            layer: rng.gen_range(0..STAR_LAYERS.len()),
+            twinkle_phase: rng.gen_range(0.0..std::f32::consts::TAU),
+            twinkle_rate: rng.gen_range(0.02..0.1),
         });
     }
     stars
 }
 
 impl EventHandler for MainState {
-    fn update(&mut self, _ctx: &mut Context) -> GameResult {
-        if !self.game_over {
-            self.lander.update();
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        // A replayed unpause has to be applied before the paused early-out
+        self.apply_replay_events(ctx)?;
+        if self.paused {
+            // Don't bank paused time, so unpausing doesn't jump the simulation
+            self.timestep.reset();
+            self.ticks_last_frame = 0;
+            self.audio.set_thrusting(ctx, false)?;
+            return Ok(());
+        }
 
-            // Check collision with terrain
-            if self.terrain.check_collision(&mut self.lander) {
-                self.game_over = true;
-                if !self.lander.is_landed_safely() {
-                    self.explosion = Some(Explosion::new(
-                        self.lander.position.x,
-                        self.lander.position.y,
-                    ));
-                }
+        let was_playing = self.state == GameState::Playing;
+        let fuel_ran_out = self.attempt.out_of_fuel_frame;
+        // Slow-motion feeds less real time in; each tick is still a full dt,
+        // so replays stay in step
+        self.time_scale = self.approach_time_scale();
+        let ticks = self
+            .timestep
+            .advance(ctx.time.delta().as_secs_f32() * self.time_scale);
+        self.ticks_last_frame = ticks;
+        for _ in 0..ticks {
+            self.apply_replay_events(ctx)?;
+            if self.paused {
+                break;
+            }
+            self.step();
+        }
+
+        // Sounds are driven from here since step() has no context
+        let thrusting =
+            self.state == GameState::Playing && self.lander.thrust > 0.0 && self.lander.fuel > 0.0;
+        self.audio.set_thrusting(ctx, thrusting)?;
+        if self.attempt.out_of_fuel_frame.is_some()
+            && self.attempt.out_of_fuel_frame != fuel_ran_out
+        {
+            self.audio.play_flameout(ctx)?;
+        }
+        if was_playing && self.state == GameState::GameOver {
+            if self.lander.is_landed_safely() {
+                self.audio.play_landing(ctx)?;
+            } else {
+                self.audio.play_explosion(ctx)?;
             }
-        } else if let Some(explosion) = &mut self.explosion {
-            explosion.update();
         }
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        let theme = self.settings.theme();
         // Create a new Canvas
-        let mut canvas = graphics::Canvas::from_frame(
-            ctx,
-            graphics::Color::from([0.0, 0.0, 0.08, 1.0]), // Dark blue background
-        );
+        let mut canvas = graphics::Canvas::from_frame(ctx, theme.background);
 
-        // Draw stars
-        for &star in &self.stars {
-            let star_mesh = graphics::Mesh::new_circle(
-                ctx,
-                graphics::DrawMode::fill(),
-                star,
-                1.0,
-                0.1,
-                Color::WHITE,
-            )?;
-            canvas.draw(&star_mesh, graphics::DrawParam::default());
+        let view = self.camera.view();
+        // Everything is laid out in logical screen units, scaled to the
+        // window and letterboxed to keep the aspect ratio
+        let screen = self.letterbox;
+        canvas.set_screen_coordinates(screen);
+
+        // Draw stars in screen space; nearer layers scroll faster for depth
+        let star_mesh = self.star_mesh(ctx)?;
+        for star in &self.stars {
+            let (parallax, radius, _) = STAR_LAYERS[star.layer];
+            canvas.draw(
+                star_mesh,
+                graphics::DrawParam::default()
+                    .dest([
+                        (star.position.x - view.x * parallax).rem_euclid(SCREEN_WIDTH),
+                        star.position.y,
+                    ])
+                    .scale([radius, radius])
+                    .color(Color {
+                        a: star.alpha(self.frame),
+                        ..theme.stars
+                    }),
+            );
+        }
+
+        // World layer is drawn through the camera, jittered while shaking
+        let mut world_view = fit_view(view, self.letterbox);
+        if self.attempt.shake_timer > 0.0 {
+            let magnitude = self.shake_magnitude * self.attempt.shake_timer / SHAKE_DURATION;
+            world_view.x += rand::thread_rng().gen_range(-1.0..=1.0) * magnitude;
+            world_view.y += rand::thread_rng().gen_range(-1.0..=1.0) * magnitude;
+        }
+        canvas.set_screen_coordinates(world_view);
+
+        if self.state == GameState::Editor {
+            canvas.set_screen_coordinates(fit_view(self.editor.view(), self.letterbox));
+            self.editor.draw(ctx, &mut canvas, &theme)?;
+            canvas.set_screen_coordinates(screen);
+            self.editor.draw_help(&mut canvas, &theme);
+            self.draw_letterbox(&mut canvas, ctx)?;
+            canvas.finish(ctx)?;
+            return Ok(());
+        }
+
+        if self.in_menu() {
+            // The attract demo plays behind the title
+            canvas.set_screen_coordinates(fit_view(self.attract.view(), self.letterbox));
+            self.attract.draw(ctx, &mut canvas, &theme)?;
+            canvas.set_screen_coordinates(screen);
+            if self.state == GameState::Settings {
+                self.draw_settings(&mut canvas);
+            } else {
+                self.draw_menu(&mut canvas);
+            }
+            self.draw_letterbox(&mut canvas, ctx)?;
+            canvas.finish(ctx)?;
+            return Ok(());
         }
 
         // Draw terrain
-        self.terrain.draw(&mut canvas)?;
+        self.terrain.draw(ctx, &mut canvas, &theme)?;
+
+        // Pulse the pad closest to the lander
+        if self.show_pad_highlight && self.state == GameState::Playing {
+            if let Some(pad) = self.terrain.model().nearest_pad(self.lander.position.x) {
+                let pulse = 0.5 + 0.5 * (self.frame as f32 * 0.1).sin();
+                self.terrain
+                    .draw_pad_highlight(ctx, &mut canvas, pad, pulse, &theme)?;
+            }
+        }
+
+        // Funnel above the target pad, lit once the lander is inside it
+        if self.show_approach_corridor && self.state == GameState::Playing {
+            if let Some(pad) = self.terrain.model().nearest_pad(self.lander.position.x) {
+                let lean = self.lander.physics.max_safe_landing_angle;
+                let color = if self
+                    .terrain
+                    .in_approach_corridor(pad, lean, self.lander.position)
+                {
+                    Color::new(0.0, 1.0, 0.0, 0.6)
+                } else {
+                    Color {
+                        a: 0.3,
+                        ..theme.hud_text
+                    }
+                };
+                self.terrain
+                    .draw_approach_corridor(ctx, &mut canvas, pad, lean, color)?;
+            }
+        }
+
+        // Draw exhaust behind the lander
+        self.exhaust.draw(ctx, &mut canvas)?;
+
+        // Draw the best run's ghost racing alongside
+        if self.state == GameState::Playing {
+            if let Some(frame) = self.ghost.as_ref().and_then(|ghost| ghost.current()) {
+                self.lander.draw_ghost(ctx, &mut canvas, frame)?;
+            }
+        }
+
+        // Draw the crash again, slowed down, during an instant replay
+        if self.state == GameState::GameOver {
+            if let Some(frame) = self.instant_replay.current() {
+                self.lander.draw_ghost(ctx, &mut canvas, frame)?;
+            }
+        }
 
         // Draw lander if not crashed
-        if !self.game_over || self.lander.is_landed_safely() {
-            self.lander.draw(ctx, &mut canvas)?;
+        if self.state == GameState::Playing || self.lander.is_landed_safely() {
+            // Smoothed between ticks while flying; the HUD still reads the
+            // latest state
+            let alpha = if self.state == GameState::Playing && self.attempt.landed_pad.is_none() {
+                self.timestep.alpha()
+            } else {
+                1.0
+            };
+            self.lander.draw(ctx, &mut canvas, &theme, alpha)?;
+        }
+
+        if self.show_velocity_vector && self.state == GameState::Playing {
+            self.lander.draw_velocity_vector(ctx, &mut canvas)?;
+        }
+
+        // What's pushing the lander around, with the debug overlay
+        if self.show_debug && self.state == GameState::Playing {
+            self.lander.draw_force_arrows(ctx, &mut canvas)?;
+        }
+
+        // Draw predicted touchdown point
+        if self.show_landing_reticle && self.state == GameState::Playing {
+            self.lander
+                .draw_landing_reticle(ctx, &mut canvas, self.terrain.model())?;
         }
 
-        // Draw explosion if crashed
-        if let Some(explosion) = &self.explosion {
+        // Draw thrust power-up
+        if self.state == GameState::Playing {
+            self.power_up.draw(ctx, &mut canvas)?;
+        }
+
+        // Draw meteors and their impacts
+        self.meteors.draw(ctx, &mut canvas)?;
+
+        // Draw crash explosions and landing dust, one batch each
+        for explosion in &mut self.explosions {
             explosion.draw(ctx, &mut canvas)?;
         }
 
+        // HUD stays fixed to the screen
+        canvas.set_screen_coordinates(screen);
+
+        // Cool tint deepening as time slows
+        if self.time_scale < 1.0 {
+            let strength = (1.0 - self.time_scale) / (1.0 - MIN_TIME_SCALE);
+            let tint = graphics::Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::fill(),
+                self.letterbox,
+                Color::new(0.2, 0.4, 0.8, 0.1 * strength),
+            )?;
+            canvas.draw(&tint, graphics::DrawParam::default());
+        }
+
         // Draw HUD
         self.draw_hud(&mut canvas, ctx)?;
+        self.draw_minimap(&mut canvas, ctx)?;
+        if self.state == GameState::Playing {
+            self.draw_offscreen_indicator(&mut canvas, ctx)?;
+        }
+
+        if self.settings.show_tutorial
+            && self.state == GameState::Playing
+            && self.replay_player.is_none()
+        {
+            self.draw_tutorial(&mut canvas, ctx)?;
+        }
+        if self.state == GameState::Summary {
+            self.draw_summary(&mut canvas, ctx)?;
+        }
+        if self.paused {
+            self.draw_pause_overlay(&mut canvas, ctx)?;
+        }
+        if self.show_debug {
+            self.draw_debug_overlay(&mut canvas, ctx);
+        }
+        if self.console.is_open() {
+            self.console
+                .draw(ctx, &mut canvas, &self.settings.theme())?;
+        }
+        self.draw_letterbox(&mut canvas, ctx)?;
 
         // Present the canvas
         canvas.finish(ctx)?;
@@ -177,43 +2462,398 @@ impl EventHandler for MainState {
         Ok(())
     }
 
+    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        self.letterbox = letterbox(width, height);
+        debug!("Resized to {}x{}", width, height);
+        Ok(())
+    }
+
     fn key_down_event(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context,
         input: KeyInput,
+        // Not needed: held controls are polled each tick from held_keys
         _repeated: bool,
     ) -> GameResult {
-        if !self.game_over {
-            match input.keycode {
-                Some(KeyCode::Up) => self.lander.apply_thrust(1.0),
-                Some(KeyCode::Left) => self.lander.rotate(-0.1),
-                Some(KeyCode::Right) => self.lander.rotate(0.1),
-                Some(KeyCode::Space) => self.lander.apply_thrust(0.5), // Half thrust option
-                Some(KeyCode::R) => {
-                    // Reset game
-                    debug!("Resetting game...");
-                    self.lander = LunarLander::new(400.0, 100.0);
-                    self.game_over = false;
-                    self.explosion = None;
+        let Some(key) = input.keycode else {
+            return Ok(());
+        };
+        // An open console takes all key presses; typing comes through
+        // text_input_event
+        if self.console.is_open() {
+            match key {
+                KeyCode::Grave | KeyCode::Escape => self.console.toggle(),
+                KeyCode::Back => self.console.backspace(),
+                KeyCode::Return | KeyCode::NumpadEnter => {
+                    if let Some(command) = self.console.submit() {
+                        self.run_console_command(command);
+                    }
                 }
                 _ => (),
             }
-        } else if let Some(KeyCode::R) = input.keycode {
-            // Allow reset even when game is over
-            self.lander = LunarLander::new(400.0, 100.0);
-            self.game_over = false;
-            self.explosion = None;
+            return Ok(());
+        }
+        match key {
+            KeyCode::Grave => self.console.toggle(),
+            KeyCode::F5 => self.save_replay(),
+            KeyCode::F3 => self.show_debug = !self.show_debug,
+            KeyCode::F9 if self.replay_player.is_some() => {
+                // Stop watching and take over from here
+                self.replay_player = None;
+                self.held_keys.clear();
+            }
+            // Live input is ignored while a replay is driving
+            _ if self.replay_player.is_some() => (),
+            _ => {
+                if !self.in_menu() {
+                    if let Some(recorder) = &mut self.replay_recorder {
+                        recorder.record(self.session_tick, key, true);
+                    }
+                }
+                self.press_key(ctx, key)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) -> GameResult {
+        if self.console.is_open() {
+            self.console.type_char(character);
         }
         Ok(())
     }
 
     fn key_up_event(&mut self, _ctx: &mut Context, input: KeyInput) -> GameResult {
-        if !self.game_over {
-            match input.keycode {
-                Some(KeyCode::Up) | Some(KeyCode::Space) => self.lander.apply_thrust(0.0),
-                _ => (),
+        let Some(key) = input.keycode else {
+            return Ok(());
+        };
+        if self.replay_player.is_some() {
+            return Ok(());
+        }
+        if !self.in_menu() {
+            if let Some(recorder) = &mut self.replay_recorder {
+                recorder.record(self.session_tick, key, false);
+            }
+        }
+        self.release_key(key);
+        Ok(())
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        let point = window_to_logical(self.letterbox, ctx.gfx.drawable_size(), x, y);
+        match self.state {
+            GameState::Editor => self.editor.mouse_down(button, point),
+            GameState::Playing if button == MouseButton::Left && !self.paused => {
+                self.mouse_position = Some(point);
+                self.mouse_thrust = self.mouse_aim().is_some();
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        _ctx: &mut Context,
+        button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) -> GameResult {
+        if self.state == GameState::Editor {
+            self.editor.mouse_up();
+        } else if button == MouseButton::Left && self.mouse_thrust {
+            self.mouse_thrust = false;
+            self.lander.set_thrust_input(0.0);
+        }
+        Ok(())
+    }
+
+    fn mouse_motion_event(
+        &mut self,
+        ctx: &mut Context,
+        x: f32,
+        y: f32,
+        _dx: f32,
+        _dy: f32,
+    ) -> GameResult {
+        let point = window_to_logical(self.letterbox, ctx.gfx.drawable_size(), x, y);
+        if self.state == GameState::Editor {
+            self.editor.mouse_moved(point);
+        }
+        self.mouse_position = Some(point);
+        Ok(())
+    }
+
+    fn gamepad_button_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        btn: Button,
+        _id: GamepadId,
+    ) -> GameResult {
+        match (self.state, btn) {
+            (GameState::Menu, Button::Start) | (GameState::Menu, Button::South) => {
+                self.start_game()
+            }
+            (GameState::Playing, Button::Start) => {
+                self.stop_recording("gamepad input");
+                self.toggle_pause();
+            }
+            (GameState::Playing, Button::RightTrigger2) if !self.paused => {
+                self.stop_recording("gamepad input");
+                self.lander.set_thrust_input(1.0);
             }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn gamepad_button_up_event(
+        &mut self,
+        _ctx: &mut Context,
+        btn: Button,
+        _id: GamepadId,
+    ) -> GameResult {
+        if self.state == GameState::Playing && !self.paused && btn == Button::RightTrigger2 {
+            self.stop_recording("gamepad input");
+            self.lander.set_thrust_input(0.0);
+        }
+        Ok(())
+    }
+
+    fn gamepad_axis_event(
+        &mut self,
+        _ctx: &mut Context,
+        axis: Axis,
+        value: f32,
+        _id: GamepadId,
+    ) -> GameResult {
+        if self.state != GameState::Playing || self.paused {
+            return Ok(());
+        }
+        if !matches!(axis, Axis::LeftStickX | Axis::LeftStickY | Axis::RightZ) {
+            return Ok(());
+        }
+        self.stop_recording("gamepad input");
+        let value = self.settings.shape_stick(value);
+        match axis {
+            // Stick x turns proportionally each tick; centering it stops turning
+            Axis::LeftStickX => self.stick_rotation = value,
+            // Pushing the stick up (or an analog trigger) gives fractional thrust,
+            // and returning it to rest cuts the engine
+            Axis::LeftStickY | Axis::RightZ => self.lander.set_thrust_input(value.max(0.0)),
+            _ => (),
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ghost::FrameState;
+
+    /// A lander judged on flat ground at the given touchdown state.
+    fn touchdown(fuel_share: f32, speed: f32, tilt: f32, on_pad: bool) -> LunarLander {
+        let mut lander = LunarLander::with_physics(400.0, 300.0, PhysicsConfig::lunar());
+        lander.fuel = lander.max_fuel * fuel_share;
+        lander.velocity = Vec2::new(0.0, -speed);
+        lander.angle = tilt;
+        lander.check_landing_safety(0.0, on_pad);
+        lander
+    }
+
+    #[test]
+    fn perfect_landing_scores_every_point() {
+        let score = landing_score(&touchdown(1.0, 0.0, 0.0, true), 0.0, 1);
+        assert_eq!(score.total, 2500);
+    }
+
+    #[test]
+    fn half_fuel_speed_and_tilt_scores_half() {
+        let score = landing_score(&touchdown(0.5, 1.0, 0.075, true), 0.0, 1);
+        assert_eq!(score.fuel, 500.0);
+        assert_eq!(score.velocity, 500.0);
+        assert!((score.attitude - 250.0).abs() < 0.01);
+        assert_eq!(score.total, 1250);
+    }
+
+    #[test]
+    fn time_bonus_and_multiplier_add_up() {
+        let score = landing_score(&touchdown(1.0, 0.0, 0.0, true), 100.0, 3);
+        assert_eq!(score.total, 7800);
+    }
+
+    #[test]
+    fn crash_scores_nothing() {
+        let score = landing_score(&touchdown(1.0, 3.0, 0.0, true), 100.0, 3);
+        assert_eq!(score.total, 0);
+    }
+
+    #[test]
+    fn same_seed_generates_identical_stars() {
+        let positions = |seed| -> Vec<_> {
+            generate_stars(seed)
+                .iter()
+                .map(|star| (star.position, star.layer))
+                .collect()
+        };
+        assert_eq!(positions(99), positions(99));
+        assert_ne!(positions(99), positions(100));
+    }
+
+    #[test]
+    fn rough_landing_keeps_a_small_share_of_the_score() {
+        let pad = landing_score(&touchdown(1.0, 1.0, 0.0, true), 0.0, 1);
+        let rough = landing_score(&touchdown(1.0, 1.0, 0.0, false), 0.0, 1);
+        assert_eq!(pad.total, 2000);
+        assert_eq!(rough.factor, ROUGH_LANDING_FACTOR);
+        assert_eq!(rough.total, 200);
+    }
+
+    #[test]
+    fn touchdown_speed_bands_land_bounce_or_crash() {
+        // Safe up to 2 m/s, bounce up to 3, crash beyond
+        let gentle = touchdown(1.0, 1.5, 0.0, true);
+        assert!(gentle.is_landed_safely());
+
+        let marginal = touchdown(1.0, 2.5, 0.0, true);
+        assert!(!marginal.is_landed_safely());
+        assert!(can_bounce(&marginal, ContactPart::Legs, 0));
+        assert!(can_bounce(&marginal, ContactPart::Legs, MAX_BOUNCES - 1));
+        assert!(!can_bounce(&marginal, ContactPart::Legs, MAX_BOUNCES));
+        assert!(!can_bounce(&marginal, ContactPart::Body, 0));
+
+        let hard = touchdown(1.0, 3.5, 0.0, true);
+        assert!(!hard.is_landed_safely());
+        assert!(!can_bounce(&hard, ContactPart::Legs, 0));
+    }
+
+    #[test]
+    fn efficiency_grades_change_at_each_fifth() {
+        let grades = [
+            (1.0, 'A'),
+            (0.8, 'A'),
+            (0.79, 'B'),
+            (0.6, 'B'),
+            (0.59, 'C'),
+            (0.4, 'C'),
+            (0.39, 'D'),
+            (0.2, 'D'),
+            (0.19, 'F'),
+            (0.0, 'F'),
+        ];
+        for (efficiency, grade) in grades {
+            assert_eq!(efficiency_grade(efficiency), grade, "at {}", efficiency);
+        }
+    }
+
+    #[test]
+    fn reset_lander_restores_every_attempt_field() {
+        let mut game = windowless_game();
+        game.start_game();
+        let frame = |x| FrameState {
+            x,
+            y: 100.0,
+            angle: 0.0,
+            thrust: 0.0,
+        };
+        game.ghost = Some(Ghost::new(GhostRun {
+            score: 500,
+            frames: vec![frame(1.0), frame(2.0), frame(3.0)],
+        }));
+
+        // Play the attempt out to a crash
+        for _ in 0..60 {
+            game.lander.apply_thrust(1.0);
+            game.lander.apply_rcs(1.0);
+            game.lander.rotate(0.1);
+            game.lander.update();
+            game.recorder.record(&game.lander);
+            game.instant_replay.record(&game.lander);
+            game.exhaust
+                .emit(&mut game.particle_rng, game.lander.position, Vec2::Y, 1.0);
+            game.power_up.update(game.lander.physics.dt);
+            if let Some(ghost) = &mut game.ghost {
+                ghost.advance();
+            }
+        }
+        game.lander.check_landing_safety(0.5, false);
+        game.state = GameState::GameOver;
+        game.explosions.push(Explosion::new(
+            &mut game.particle_rng,
+            game.lander.position.x,
+            game.lander.position.y,
+            game.lander.physics.gravity,
+            game.settings.theme().explosion,
+        ));
+        game.instant_replay.start();
+        game.attempt = Attempt {
+            elapsed: 42.0,
+            out_of_fuel_frame: Some(900),
+            landed_pad: Some(1),
+            clearing_surface: true,
+            visited_pads: vec![0, 2],
+            bounces: MAX_BOUNCES,
+            game_over_time: 3.0,
+            last_landing: Some(ScoreBreakdown::default()),
+            shake_timer: 0.5,
+        };
+
+        game.reset_lander();
+
+        let lander = &game.lander;
+        assert_eq!(lander.position, game.spawn);
+        assert_eq!(lander.velocity, Vec2::ZERO);
+        assert_eq!(lander.angle, 0.0);
+        assert_eq!(lander.angular_velocity, 0.0);
+        assert_eq!(lander.thrust, 0.0);
+        assert_eq!(lander.rcs, 0.0);
+        assert_eq!(lander.fuel, game.level_fuel);
+        assert_eq!(lander.fuel_used, 0.0);
+        assert!(!lander.is_landing_checked());
+        assert!(!lander.is_landed_safely());
+        assert_eq!(game.exhaust.particle_count(), 0);
+        assert!(game.explosions.is_empty());
+        assert_eq!(
+            game.power_up,
+            PowerUp::for_level(game.seed, game.terrain.model(), POWER_UP_RESPAWN)
+        );
+        assert_eq!(game.attempt, Attempt::default());
+        assert_eq!(game.state, GameState::Playing);
+        assert!(game.recorder.take_run(0).frames.is_empty());
+        assert!(!game.instant_replay.is_playing());
+        // Nothing recorded is left to replay
+        game.instant_replay.start();
+        assert!(!game.instant_replay.is_playing());
+        let ghost = game.ghost.as_ref().and_then(Ghost::current);
+        assert_eq!(ghost.map(|frame| frame.x), Some(1.0));
+    }
+
+    fn windowless_game() -> MainState {
+        MainState::build(
+            None,
+            false,
+            AudioState::default(),
+            graphics::Rect::new(0.0, 0.0, SCREEN_WIDTH, SCREEN_HEIGHT),
+        )
+    }
+
+    #[test]
+    fn mouse_aim_stops_replay_recording() {
+        let mut game = windowless_game();
+        game.start_game();
+        game.step();
+        assert!(game.replay_recorder.is_some());
+
+        game.settings.control_scheme = ControlScheme::MouseAim;
+        game.mouse_position = Some(Point2 { x: 0.0, y: 0.0 });
+        game.step();
+        assert!(game.replay_recorder.is_none());
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::VecDeque;
+use std::fs;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::config::config_path;
+use crate::lander::LunarLander;
+
+const GHOST_FILE: &str = "ghost.toml";
+const INSTANT_REPLAY_FRAMES: usize = 300; // 5 s at 60 Hz
+const INSTANT_REPLAY_SPEED: f32 = 0.5; // recorded frames per tick on playback
+
+/// The lander's pose on one physics tick.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct FrameState {
+    pub x: f32,
+    pub y: f32,
+    pub angle: f32,
+    pub thrust: f32,
+}
+
+impl FrameState {
+    pub fn of(lander: &LunarLander) -> Self {
+        FrameState {
+            x: lander.position.x,
+            y: lander.position.y,
+            angle: lander.angle,
+            thrust: if lander.fuel > 0.0 {
+                lander.thrust
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+/// A recorded landing and the score it earned.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhostRun {
+    pub score: u32,
+    pub frames: Vec<FrameState>,
+}
+
+impl GhostRun {
+    /// Returns the saved best run, if there is a readable one.
+    pub fn load() -> Option<Self> {
+        let path = config_path(GHOST_FILE)?;
+        let contents = fs::read_to_string(&path).ok()?;
+        match toml::from_str(&contents) {
+            Ok(run) => Some(run),
+            Err(e) => {
+                warn!("Ignoring invalid ghost in {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = config_path(GHOST_FILE) else {
+            return;
+        };
+        let contents = match toml::to_string(self) {
+            Ok(contents) => contents,
+            Err(e) => {
+                warn!("Failed to serialize ghost: {}", e);
+                return;
+            }
+        };
+        if let Some(dir) = path.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                warn!("Failed to create {}: {}", dir.display(), e);
+                return;
+            }
+        }
+        if let Err(e) = fs::write(&path, contents) {
+            warn!("Failed to save ghost to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Collects the current attempt one tick at a time.
+#[derive(Default)]
+pub struct Recorder {
+    frames: Vec<FrameState>,
+}
+
+impl Recorder {
+    pub fn record(&mut self, lander: &LunarLander) {
+        self.frames.push(FrameState::of(lander));
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Hands over the recorded attempt, leaving the recorder empty.
+    pub fn take_run(&mut self, score: u32) -> GhostRun {
+        GhostRun {
+            score,
+            frames: std::mem::take(&mut self.frames),
+        }
+    }
+}
+
+/// Plays a `GhostRun` back in step with the live lander.
+pub struct Ghost {
+    run: GhostRun,
+    tick: usize,
+}
+
+impl Ghost {
+    pub fn new(run: GhostRun) -> Self {
+        info!("Ghost run scoring {} ready", run.score);
+        Ghost { run, tick: 0 }
+    }
+
+    pub fn score(&self) -> u32 {
+        self.run.score
+    }
+
+    pub fn restart(&mut self) {
+        self.tick = 0;
+    }
+
+    pub fn advance(&mut self) {
+        self.tick += 1;
+    }
+
+    /// The ghost's pose this tick, or `None` once its run has ended.
+    pub fn current(&self) -> Option<&FrameState> {
+        self.run.frames.get(self.tick)
+    }
+}
+
+/// The last few seconds of flight, kept so a crash can be watched again in
+/// slow motion.
+#[derive(Default)]
+pub struct InstantReplay {
+    frames: VecDeque<FrameState>,
+    playhead: Option<f32>, // fractional frame index while playing back
+}
+
+impl InstantReplay {
+    pub fn record(&mut self, lander: &LunarLander) {
+        if self.frames.len() == INSTANT_REPLAY_FRAMES {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(FrameState::of(lander));
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.playhead = None;
+    }
+
+    pub fn start(&mut self) {
+        if !self.frames.is_empty() {
+            self.playhead = Some(0.0);
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playhead.is_some()
+    }
+
+    /// Moves playback on by one tick. Returns the final frame on the tick
+    /// playback reaches it.
+    pub fn advance(&mut self) -> Option<FrameState> {
+        let playhead = self.playhead.as_mut()?;
+        *playhead += INSTANT_REPLAY_SPEED;
+        if (*playhead as usize) < self.frames.len() {
+            return None;
+        }
+        self.playhead = None;
+        self.frames.back().copied()
+    }
+
+    /// The pose to show this tick while playing back.
+    pub fn current(&self) -> Option<&FrameState> {
+        self.frames.get(self.playhead? as usize)
+    }
+}
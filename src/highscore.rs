@@ -0,0 +1,69 @@
+use std::fs;
+use std::path::PathBuf;
+
+use log::warn;
+
+use crate::config::config_path;
+use crate::daily::Date;
+
+fn high_score_path() -> Option<PathBuf> {
+    config_path("highscore.txt")
+}
+
+/// Returns the saved best score, or 0 if the file is missing or corrupt.
+pub fn load_high_score() -> u32 {
+    high_score_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| contents.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+pub fn save_high_score(score: u32) {
+    let Some(path) = high_score_path() else {
+        warn!("No config directory available, high score not saved");
+        return;
+    };
+
+    if let Some(dir) = path.parent() {
+        if let Err(e) = fs::create_dir_all(dir) {
+            warn!("Failed to create {}: {}", dir.display(), e);
+            return;
+        }
+    }
+    if let Err(e) = fs::write(&path, score.to_string()) {
+        warn!("Failed to save high score to {}: {}", path.display(), e);
+    }
+}
+
+fn daily_best_path() -> Option<PathBuf> {
+    config_path("daily_best.txt")
+}
+
+/// Returns the best score on `date`'s daily challenge, or 0 if it hasn't
+/// been played yet. Only the latest day played is kept.
+pub fn load_daily_best(date: Date) -> u32 {
+    daily_best_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| {
+            let (day, score) = contents.trim().split_once(' ')?;
+            (day == date.to_string()).then(|| score.parse().ok())?
+        })
+        .unwrap_or(0)
+}
+
+pub fn save_daily_best(date: Date, score: u32) {
+    let Some(path) = daily_best_path() else {
+        warn!("No config directory available, daily best not saved");
+        return;
+    };
+
+    if let Some(dir) = path.parent() {
+        if let Err(e) = fs::create_dir_all(dir) {
+            warn!("Failed to create {}: {}", dir.display(), e);
+            return;
+        }
+    }
+    if let Err(e) = fs::write(&path, format!("{} {}", date, score)) {
+        warn!("Failed to save daily best to {}: {}", path.display(), e);
+    }
+}
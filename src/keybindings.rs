@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::fs;
+
+use ggez::input::keyboard::KeyCode;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::config::config_path;
+
+const KEY_BINDINGS_FILE: &str = "keybindings.toml";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Thrust,
+    HalfThrust,
+    RotateLeft,
+    RotateRight,
+    RcsLeft,
+    RcsRight,
+    Restart,
+    Pause,
+}
+
+impl Action {
+    const ALL: [Action; 8] = [
+        Action::Thrust,
+        Action::HalfThrust,
+        Action::RotateLeft,
+        Action::RotateRight,
+        Action::RcsLeft,
+        Action::RcsRight,
+        Action::Restart,
+        Action::Pause,
+    ];
+}
+
+/// Keys bound to each action. Missing entries in the file fall back to the
+/// defaults, so a bindings file only needs to list what it changes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyBindings {
+    thrust: Vec<KeyCode>,
+    half_thrust: Vec<KeyCode>,
+    rotate_left: Vec<KeyCode>,
+    rotate_right: Vec<KeyCode>,
+    rcs_left: Vec<KeyCode>,
+    rcs_right: Vec<KeyCode>,
+    restart: Vec<KeyCode>,
+    pause: Vec<KeyCode>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            thrust: vec![KeyCode::Up],
+            half_thrust: vec![KeyCode::Space],
+            rotate_left: vec![KeyCode::Left],
+            rotate_right: vec![KeyCode::Right],
+            rcs_left: vec![KeyCode::A, KeyCode::Comma],
+            rcs_right: vec![KeyCode::D, KeyCode::Period],
+            restart: vec![KeyCode::R],
+            pause: vec![KeyCode::Escape, KeyCode::P],
+        }
+    }
+}
+
+impl KeyBindings {
+    /// Loads bindings from the config dir, writing out the defaults on first
+    /// run. A corrupt or conflicting file falls back to the defaults.
+    pub fn load() -> Self {
+        let Some(path) = config_path(KEY_BINDINGS_FILE) else {
+            return Self::default();
+        };
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(_) => {
+                info!("No key bindings at {}, writing defaults", path.display());
+                Self::default().save();
+                return Self::default();
+            }
+        };
+
+        let bindings: KeyBindings = match toml::from_str(&contents) {
+            Ok(bindings) => bindings,
+            Err(e) => {
+                warn!("Invalid key bindings in {}: {}", path.display(), e);
+                return Self::default();
+            }
+        };
+        if let Err(e) = bindings.validate() {
+            warn!("Ignoring key bindings in {}: {}", path.display(), e);
+            return Self::default();
+        }
+        bindings
+    }
+
+    pub fn save(&self) {
+        let Some(path) = config_path(KEY_BINDINGS_FILE) else {
+            return;
+        };
+        let contents = match toml::to_string_pretty(self) {
+            Ok(contents) => contents,
+            Err(e) => {
+                warn!("Failed to serialize key bindings: {}", e);
+                return;
+            }
+        };
+        if let Some(dir) = path.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                warn!("Failed to create {}: {}", dir.display(), e);
+                return;
+            }
+        }
+        if let Err(e) = fs::write(&path, contents) {
+            warn!("Failed to save key bindings to {}: {}", path.display(), e);
+        }
+    }
+
+    /// Rejects bindings where one key would trigger two actions.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut bound: HashMap<KeyCode, Action> = HashMap::new();
+        for action in Action::ALL {
+            for &key in self.keys(action) {
+                if let Some(other) = bound.insert(key, action) {
+                    if other != action {
+                        return Err(format!(
+                            "{:?} is bound to both {:?} and {:?}",
+                            key, other, action
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        match action {
+            Action::Thrust => &self.thrust,
+            Action::HalfThrust => &self.half_thrust,
+            Action::RotateLeft => &self.rotate_left,
+            Action::RotateRight => &self.rotate_right,
+            Action::RcsLeft => &self.rcs_left,
+            Action::RcsRight => &self.rcs_right,
+            Action::Restart => &self.restart,
+            Action::Pause => &self.pause,
+        }
+    }
+
+    pub fn action_for(&self, key: KeyCode) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|&action| self.keys(action).contains(&key))
+    }
+}
@@ -1,172 +1,757 @@
-use ggez::graphics::{self, Canvas, Color, DrawMode, Mesh, MeshBuilder};
+use std::cell::OnceCell;
+use std::collections::VecDeque;
+
+use ggez::graphics::{self, BlendMode, Canvas, Color, DrawMode, DrawParam, Mesh, MeshBuilder};
 use ggez::mint::Point2;
 use ggez::{Context, GameResult};
 use glam::Vec2;
 use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::difficulty::DifficultySettings;
+use crate::ghost::FrameState;
+use crate::physics::PhysicsConfig;
+use crate::screen::SCREEN_HEIGHT;
+use crate::terrain::TerrainModel;
+use crate::terrain::WORLD_WIDTH;
+use crate::theme::Theme;
+
+const RCS_POWER: f32 = 1.0;
+const THRUST_FUEL_RATE: f32 = 30.0; // per second at full thrust
+const RCS_FUEL_RATE: f32 = 6.0; // per second
+const MAX_RCS_SPEED: f32 = 5.0; // m/s
+const MIN_ASSIST_LIFT: f32 = 0.1; // hover assist gives up when tipped further than this
+const MAX_SAFE_ANGULAR_VELOCITY: f32 = 0.5; // rad/s
+const ANGULAR_IMPULSE: f32 = 5.0; // rad/s gained per unit of rotate()
+const ANGULAR_DAMPING: f32 = 0.98; // per tick
+const THRUST_RAMP_TIME: f32 = 1.0; // seconds for the ramp curve to reach full input
+const BOOST_MULTIPLIER: f32 = 1.5; // thrust power while a boost is active
+const CEILING_PULL: f32 = 0.05; // m/s² downward per pixel above the top, in nudge mode
+
+// Lander geometry in local space (unrotated, relative to `position`). The
+// shapes are laid out on a grid 5/3 px below the body's centroid and raised
+// by `CENTROID_LIFT`, so the origin is the centroid, its center of mass; the
+// lander turns about that and the legs stay mirror images either side of it.
+// Body is nose, left side, right side; the RCS flame is mirrored for the left.
+const CENTROID_LIFT: f32 = 5.0 / 3.0;
+const BODY_VERTICES: [[f32; 2]; 3] = [
+    [0.0, 15.0 + CENTROID_LIFT],
+    [-10.0, -10.0 + CENTROID_LIFT],
+    [10.0, -10.0 + CENTROID_LIFT],
+];
+const LEG_POINTS: [[f32; 2]; 2] = [[-15.0, -5.0 + CENTROID_LIFT], [15.0, -5.0 + CENTROID_LIFT]];
+const FLAME_VERTICES: [[f32; 2]; 3] = [
+    [-5.0, -8.0 + CENTROID_LIFT],
+    [5.0, -8.0 + CENTROID_LIFT],
+    [0.0, -20.0 + CENTROID_LIFT],
+];
+const RCS_FLAME_VERTICES: [[f32; 2]; 3] = [
+    [8.0, -6.0 + CENTROID_LIFT],
+    [8.0, -2.0 + CENTROID_LIFT],
+    [16.0, -4.0 + CENTROID_LIFT],
+];
+const NOZZLE: [f32; 2] = [0.0, -8.0 + CENTROID_LIFT];
+const NOZZLE_VERTICES: [[f32; 2]; 4] = [
+    [-3.0, -10.0 + CENTROID_LIFT],
+    [3.0, -10.0 + CENTROID_LIFT],
+    [4.5, -13.0 + CENTROID_LIFT],
+    [-4.5, -13.0 + CENTROID_LIFT],
+];
+const LEG_WIDTH: f32 = 2.5;
+const OUTLINE_WIDTH: f32 = 1.5;
+const OUTLINE_SHADE: f32 = 0.55; // outline brightness relative to the body color
+const GLOW_LAYERS: [f32; 3] = [1.4, 1.9, 2.5]; // flame scale of each glow layer, about the nozzle
+const GLOW_ALPHA: f32 = 0.25; // per layer at full thrust; overlaps build toward the core
+
+const WRAP_DRAW_MARGIN: f32 = 20.0; // Draw a second copy this close to an edge
+
+const GHOST_ALPHA: f32 = 0.3;
+
+const AUTOPILOT_FLARE_ALTITUDE: f32 = 25.0; // level out below this height (px)
+const AUTOPILOT_MAX_DRIFT: f32 = 4.0; // m/s cap on commanded horizontal speed
+
+const MAX_PREDICTION_STEPS: usize = 1800; // 30 s of coasting at 60 Hz
+const RETICLE_SIZE: f32 = 6.0;
+
+const TRAIL_DOT_RADIUS: f32 = 1.5;
+const TRAIL_ALPHA: f32 = 0.6; // of the newest dot; older ones fade toward 0
+
+const VELOCITY_ARROW_SCALE: f32 = 10.0; // pixels per m/s
+const FORCE_ARROW_SCALE: f32 = 20.0; // pixels per m/s² of acceleration
+const MAX_VELOCITY_ARROW_LENGTH: f32 = 150.0;
+
+/// What happens when the lander reaches the left or right edge of the field.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum EdgeMode {
+    /// Stop at the wall (classic behavior).
+    Clamp,
+    /// Leave one side and re-enter on the other, keeping velocity.
+    Wrap,
+}
+
+/// What happens when the lander reaches the top of the screen.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum CeilingMode {
+    /// Stop at the top, losing any upward speed.
+    #[default]
+    Clamp,
+    /// Fly on past the top, pulled back harder the higher it goes.
+    Nudge,
+}
+
+/// How the player's thrust input maps to engine output.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum ThrustCurve {
+    /// Output follows the input directly.
+    #[default]
+    Linear,
+    /// Output is the input squared, for finer control at low throttle.
+    Quadratic,
+    /// Output builds from nothing up to the input over `THRUST_RAMP_TIME`
+    /// while it's held.
+    Ramp,
+}
+
+impl ThrustCurve {
+    const ALL: [ThrustCurve; 3] = [
+        ThrustCurve::Linear,
+        ThrustCurve::Quadratic,
+        ThrustCurve::Ramp,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ThrustCurve::Linear => "Linear",
+            ThrustCurve::Quadratic => "Quadratic",
+            ThrustCurve::Ramp => "Ramp",
+        }
+    }
+
+    /// The next curve in the list, wrapping at either end.
+    pub fn cycle(self, forward: bool) -> Self {
+        let count = Self::ALL.len();
+        let index = Self::ALL.iter().position(|&c| c == self).unwrap_or(0);
+        Self::ALL[if forward {
+            (index + 1) % count
+        } else {
+            (index + count - 1) % count
+        }]
+    }
+
+    /// Engine output for `input` (0..1) that has been held for `held` seconds.
+    fn shape(self, input: f32, held: f32) -> f32 {
+        match self {
+            ThrustCurve::Linear => input,
+            ThrustCurve::Quadratic => input * input,
+            ThrustCurve::Ramp => input * (held / THRUST_RAMP_TIME).min(1.0),
+        }
+    }
+}
 
-const GRAVITY: f32 = 1.62; // Lunar gravity (m/s²)
-const THRUST_POWER: f32 = 3.5;
-const MAX_SAFE_LANDING_VELOCITY: f32 = 2.0; // m/s
-const MAX_SAFE_LANDING_ANGLE: f32 = 0.15; // radians (approximately 8.6 degrees)
-const DT: f32 = 1.0 / 60.0; // 60 FPS
+/// Everything from outside the lander that drives one `LunarLander::step`:
+/// the controls, and the wind and drag it flies through.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LanderInput {
+    pub thrust: f32,   // main engine throttle, 0..1, after any curve or assist
+    pub rotation: f32, // as passed to `rotate`, positive clockwise on screen
+    pub rcs: f32,      // lateral thrusters, -1..1, positive pushes right
+    pub boost: bool,   // main engine runs at boosted power
+    pub wind: f32,     // horizontal push in m/s², positive to the right
+    pub drag: f32,     // atmospheric drag coefficient, 0 in vacuum
+}
 
+// Axis conventions: `position` is in screen space (y grows downward), while
+// `velocity` is in world space (y grows upward). Gravity therefore decreases
+// velocity.y, and position.y is integrated with the opposite sign.
 pub struct LunarLander {
     pub position: Point2<f32>,
     pub velocity: Vec2,
     pub angle: f32,
+    pub angular_velocity: f32,
     pub thrust: f32,
+    pub rcs: f32,
     pub fuel: f32,
+    pub max_fuel: f32,
+    pub fuel_used: f32, // burned since spawning, refuels notwithstanding
+    pub physics: PhysicsConfig,
+    pub drag: f32,
+    pub wind: f32, // horizontal push in m/s², positive to the right
+    instant_rotation: bool,
+    assist: bool,
+    boost_left: f32, // seconds of boosted thrust remaining
+    thrust_curve: ThrustCurve,
+    thrust_input: f32,    // player's throttle before the curve
+    thrust_held: f32,     // seconds the throttle has been open
+    queued_rotation: f32, // rotate() amounts waiting for the next update
+    ran_dry: bool,        // the tank emptied during the latest update
+    edge_mode: EdgeMode,
+    ceiling_mode: CeilingMode,
     landing_safety_checked: bool,
     landed_safely: bool,
+    landed_on_pad: bool,
+    landing_angle_error: f32,
+    previous_legs: Vec<Point2<f32>>,
+    previous_position: Point2<f32>, // pose at the start of the last tick, for interpolation
+    previous_angle: f32,
+    trail: VecDeque<Point2<f32>>, // recent positions, oldest first
+    trail_length: usize,
+    meshes: OnceCell<LanderMeshes>,
+}
+
+/// Local-space meshes built once and placed each frame with a `DrawParam`.
+struct LanderMeshes {
+    body: Mesh,
+    outline: Mesh,
+    nozzle: Mesh,
+    flame: Mesh,
+    glow: Mesh,
+    rcs_flame: Mesh,
+}
+
+impl LanderMeshes {
+    fn new(ctx: &mut Context) -> GameResult<Self> {
+        let body = BODY_VERTICES.map(Point2::from);
+        let legs = LEG_POINTS.map(Point2::from);
+
+        let mut mb = MeshBuilder::new();
+        mb.polygon(DrawMode::fill(), &body, Color::WHITE)?;
+        mb.line(&[legs[0], body[1]], LEG_WIDTH, Color::WHITE)?;
+        mb.line(&[legs[1], body[2]], LEG_WIDTH, Color::WHITE)?;
+        let body_mesh = Mesh::from_data(ctx, mb.build());
+
+        // Outline and nozzle are darker shades of the body's tint
+        let shade = Color::new(OUTLINE_SHADE, OUTLINE_SHADE, OUTLINE_SHADE, 1.0);
+        let mut mb = MeshBuilder::new();
+        mb.polygon(DrawMode::stroke(OUTLINE_WIDTH), &body, shade)?;
+        let outline = Mesh::from_data(ctx, mb.build());
+        let mut mb = MeshBuilder::new();
+        mb.polygon(DrawMode::fill(), &NOZZLE_VERTICES.map(Point2::from), shade)?;
+        let nozzle = Mesh::from_data(ctx, mb.build());
+
+        // Body and main flame are built white and tinted by the theme; flame
+        // alpha comes from the DrawParam color
+        let mut mb = MeshBuilder::new();
+        mb.polygon(
+            DrawMode::fill(),
+            &FLAME_VERTICES.map(Point2::from),
+            Color::WHITE,
+        )?;
+        let flame = Mesh::from_data(ctx, mb.build());
+
+        // Soft halo: ever larger copies of the flame grown out of the nozzle
+        let mut mb = MeshBuilder::new();
+        for scale in GLOW_LAYERS {
+            let layer = FLAME_VERTICES.map(|[x, y]| Point2 {
+                x: NOZZLE[0] + (x - NOZZLE[0]) * scale,
+                y: NOZZLE[1] + (y - NOZZLE[1]) * scale,
+            });
+            mb.polygon(DrawMode::fill(), &layer, Color::WHITE)?;
+        }
+        let glow = Mesh::from_data(ctx, mb.build());
+
+        let mut mb = MeshBuilder::new();
+        mb.polygon(
+            DrawMode::fill(),
+            &RCS_FLAME_VERTICES.map(Point2::from),
+            Color::new(0.6, 0.8, 1.0, 1.0),
+        )?;
+        let rcs_flame = Mesh::from_data(ctx, mb.build());
+
+        Ok(LanderMeshes {
+            body: body_mesh,
+            outline,
+            nozzle,
+            flame,
+            glow,
+            rcs_flame,
+        })
+    }
 }
 
 impl LunarLander {
-    pub fn new(x: f32, y: f32) -> Self {
-        LunarLander {
+    pub fn new(x: f32, y: f32, settings: &DifficultySettings) -> Self {
+        let mut lander = Self::with_physics(x, y, settings.physics);
+        lander.fuel = settings.fuel;
+        lander.max_fuel = settings.fuel;
+        lander
+    }
+
+    pub fn with_physics(x: f32, y: f32, physics: PhysicsConfig) -> Self {
+        let mut lander = LunarLander {
             position: Point2 { x, y },
             velocity: Vec2::ZERO,
             angle: 0.0,
+            angular_velocity: 0.0,
             thrust: 0.0,
+            rcs: 0.0,
             fuel: 100.0,
+            max_fuel: 100.0,
+            fuel_used: 0.0,
+            physics,
+            drag: 0.0,
+            wind: 0.0,
+            instant_rotation: false,
+            assist: false,
+            boost_left: 0.0,
+            thrust_curve: ThrustCurve::Linear,
+            thrust_input: 0.0,
+            thrust_held: 0.0,
+            queued_rotation: 0.0,
+            ran_dry: false,
+            edge_mode: EdgeMode::Clamp,
+            ceiling_mode: CeilingMode::Clamp,
             landing_safety_checked: false,
             landed_safely: false,
-        }
+            landed_on_pad: false,
+            landing_angle_error: 0.0,
+            previous_legs: Vec::new(),
+            previous_position: Point2 { x, y },
+            previous_angle: 0.0,
+            trail: VecDeque::new(),
+            trail_length: 0,
+            meshes: OnceCell::new(),
+        };
+        lander.previous_legs = lander.get_legs_points();
+        lander
     }
 
+    /// Advances one tick: gathers the controls set by `apply_thrust`,
+    /// `set_thrust_input`, `apply_rcs` and `rotate`, shaped by the thrust
+    /// curve and hover assist, into a `LanderInput` for `step`.
     pub fn update(&mut self) {
-        if self.fuel > 0.0 && self.thrust > 0.0 {
+        let PhysicsConfig { gravity, dt, .. } = self.physics;
+        if self.thrust_input > 0.0 {
+            self.thrust_held += dt;
+            self.apply_thrust(self.thrust_curve.shape(self.thrust_input, self.thrust_held));
+        }
+        let input = LanderInput {
+            thrust: self.commanded_thrust(),
+            rotation: std::mem::take(&mut self.queued_rotation),
+            rcs: self.rcs,
+            boost: self.boost_left > 0.0,
+            wind: self.wind,
+            drag: self.drag,
+        };
+        self.boost_left = (self.boost_left - dt).max(0.0);
+        self.step(input, dt, gravity);
+    }
+
+    /// Advances the simulation by `dt` seconds under `gravity`. The result
+    /// depends only on `input`, `dt`, `gravity` and the lander's own fields,
+    /// among them the `instant_rotation`, `edge_mode` and `ceiling_mode`
+    /// flags and the trail length, so the same inputs from the same state
+    /// always give the same result. Besides motion and fuel it updates the
+    /// previous pose and legs, the trail and `ran_dry`, and zeroes `thrust`
+    /// and `rcs` when the tank empties; read it all back from the public
+    /// fields.
+    pub fn step(&mut self, input: LanderInput, dt: f32, gravity: f32) {
+        if input.rotation != 0.0 {
+            self.spin(input.rotation);
+        }
+        self.previous_legs = self.get_legs_points();
+        self.previous_position = self.position;
+        self.previous_angle = self.angle;
+        let had_fuel = self.has_fuel();
+        if self.trail_length > 0 {
+            if self.trail.len() == self.trail_length {
+                self.trail.pop_front();
+            }
+            self.trail.push_back(self.position);
+        }
+        let thrust_power = self.engine_power(input.boost);
+        let commanded = input.thrust.clamp(0.0, 1.0);
+        let rcs = input.rcs.clamp(-1.0, 1.0);
+
+        if self.has_fuel() && commanded > 0.0 {
+            // On the last drop, only the thrust the remaining fuel pays for
+            let burn = commanded * THRUST_FUEL_RATE * dt;
+            let thrust = commanded * (self.fuel / burn).min(1.0);
+
             // Apply thrust
             let thrust_vector = Vec2::new(
-                -self.thrust * self.angle.cos() * THRUST_POWER, // Negative because right is positive x
-                self.thrust * self.angle.sin() * THRUST_POWER,  // Positive because up is positive y
+                -thrust * self.angle.cos() * thrust_power, // Negative because right is positive x
+                thrust * self.angle.sin() * thrust_power,  // World space: up is positive y
             );
 
             info!(
                 "Thrust: {}, Angle: {}, Vector: {:?}",
-                self.thrust, self.angle, thrust_vector
+                thrust, self.angle, thrust_vector
             ); // Debug
 
-            self.velocity += thrust_vector * DT;
-            self.fuel -= self.thrust * 0.5;
+            self.velocity += thrust_vector * dt;
+            self.fuel_used += burn.min(self.fuel);
+            self.fuel = (self.fuel - burn).max(0.0);
+        }
+
+        if self.has_fuel() && rcs != 0.0 {
+            let burn = rcs.abs() * RCS_FUEL_RATE * dt;
+            let rcs = rcs * (self.fuel / burn).min(1.0);
+
+            // Apply lateral RCS impulse, never pushing past MAX_RCS_SPEED
+            let limit = MAX_RCS_SPEED.max(self.velocity.x.abs());
+            self.velocity.x = (self.velocity.x + rcs * RCS_POWER * dt).clamp(-limit, limit);
+            self.fuel_used += burn.min(self.fuel);
+            self.fuel = (self.fuel - burn).max(0.0);
+        }
+
+        // Engines flame out with the tank
+        self.ran_dry = had_fuel && !self.has_fuel();
+        if !self.has_fuel() {
+            self.thrust = 0.0;
+            self.rcs = 0.0;
+        }
+
+        // Integrate rotation, with mild damping so the lander settles
+        self.angle = (self.angle + self.angular_velocity * dt) % (2.0 * std::f32::consts::PI);
+        self.angular_velocity *= ANGULAR_DAMPING;
+
+        // Apply gravity (pulls world-space velocity downward)
+        self.velocity.y -= gravity * dt;
+        self.velocity.x += input.wind * dt;
+
+        // Apply atmospheric drag (opposes velocity, proportional to speed squared)
+        if input.drag > 0.0 {
+            let speed = self.velocity.length();
+            self.velocity -= self.velocity * (input.drag * speed * dt).min(1.0);
+        }
+
+        // Above the top in nudge mode, a spring pulls it back down
+        if self.ceiling_mode == CeilingMode::Nudge && self.position.y < 0.0 {
+            self.velocity.y += CEILING_PULL * self.position.y * dt;
         }
 
-        // Apply gravity
-        //self.velocity.y -= GRAVITY * DT;
-        // Should be
-        self.velocity.y -= GRAVITY * DT; // Add gravity since positive y is up
+        // Update position (screen y is flipped relative to world y)
+        self.position.x += self.velocity.x * dt;
+        self.position.y -= self.velocity.y * dt;
 
-        // Update position
-        self.position.x += self.velocity.x * DT;
-        self.position.y -= self.velocity.y * DT;
+        if self.ceiling_mode == CeilingMode::Clamp && self.position.y < 0.0 {
+            self.position.y = 0.0;
+            self.velocity.y = self.velocity.y.min(0.0);
+        }
 
         // Keep lander in bounds
-        self.position.x = self.position.x.clamp(0.0, 800.0);
+        match self.edge_mode {
+            EdgeMode::Clamp => self.position.x = self.position.x.clamp(0.0, WORLD_WIDTH),
+            EdgeMode::Wrap => {
+                let wrapped = wrap_x(self.position.x);
+                let shift = wrapped - self.position.x;
+                self.position.x = wrapped;
+                // Move last tick's legs too so the collision sweep doesn't
+                // stretch across the whole field
+                for leg in &mut self.previous_legs {
+                    leg.x += shift;
+                }
+            }
+        }
     }
 
-    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
-        // Draw lander body
-        let body_mesh = self.create_body_mesh(ctx)?;
-        canvas.draw(&body_mesh, graphics::DrawParam::default());
+    /// Draws the lander `alpha` (0..1) of the way from its pose at the start
+    /// of the last tick to its current one, so motion looks smooth when
+    /// frames fall between ticks.
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        theme: &Theme,
+        alpha: f32,
+    ) -> GameResult {
+        self.draw_trail(ctx, canvas)?;
+        let (position, angle) = self.interpolated_pose(alpha);
+        self.draw_at(ctx, canvas, position, angle, theme)?;
 
-        // Draw thrust flame if thrusting
-        if self.thrust > 0.0 && self.fuel > 0.0 {
-            let flame_mesh = self.create_flame_mesh(ctx)?;
-            canvas.draw(&flame_mesh, graphics::DrawParam::default());
+        // Show the part that has already wrapped onto the opposite edge
+        if self.edge_mode == EdgeMode::Wrap {
+            let shift = if position.x < WRAP_DRAW_MARGIN {
+                WORLD_WIDTH
+            } else if position.x > WORLD_WIDTH - WRAP_DRAW_MARGIN {
+                -WORLD_WIDTH
+            } else {
+                0.0
+            };
+            if shift != 0.0 {
+                let copy = Point2 {
+                    x: position.x + shift,
+                    y: position.y,
+                };
+                self.draw_at(ctx, canvas, copy, angle, theme)?;
+            }
         }
 
         Ok(())
     }
 
-    fn create_body_mesh(&self, ctx: &mut Context) -> GameResult<Mesh> {
-        let points = self.get_vertices();
-        let legs = self.get_legs_points();
+    fn interpolated_pose(&self, alpha: f32) -> (Point2<f32>, f32) {
+        let from = Vec2::from(self.previous_position);
+        let to = Vec2::from(self.position);
+        // Don't sweep across the world on the tick the lander wraps
+        let position = if (to.x - from.x).abs() > WORLD_WIDTH / 2.0 {
+            to
+        } else {
+            from.lerp(to, alpha)
+        };
+        // Turn the short way round
+        let turn = (self.angle - self.previous_angle + std::f32::consts::PI)
+            .rem_euclid(std::f32::consts::TAU)
+            - std::f32::consts::PI;
+        (
+            Point2::from(position.to_array()),
+            self.previous_angle + turn * alpha,
+        )
+    }
 
+    /// Recent path as dots fading with age. Dots rather than a line, so a
+    /// wrap across the world edge doesn't draw a streak across the screen.
+    fn draw_trail(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        if self.trail.is_empty() {
+            return Ok(());
+        }
         let mut mb = MeshBuilder::new();
+        for (i, &point) in self.trail.iter().enumerate() {
+            let alpha = TRAIL_ALPHA * (i + 1) as f32 / self.trail.len() as f32;
+            mb.circle(
+                DrawMode::fill(),
+                point,
+                TRAIL_DOT_RADIUS,
+                0.1,
+                Color::new(1.0, 1.0, 1.0, alpha),
+            )?;
+        }
+        canvas.draw(&Mesh::from_data(ctx, mb.build()), DrawParam::default());
+        Ok(())
+    }
+
+    /// Draws a translucent copy of the lander at a recorded pose.
+    pub fn draw_ghost(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        frame: &FrameState,
+    ) -> GameResult {
+        let meshes = self.meshes(ctx)?;
+        let param = DrawParam::default()
+            .dest([frame.x, frame.y])
+            .rotation(frame.angle);
+        canvas.draw(
+            &meshes.body,
+            param.color(Color::new(0.6, 0.8, 1.0, GHOST_ALPHA)),
+        );
+        if frame.thrust > 0.0 {
+            canvas.draw(
+                &meshes.flame,
+                param.color(Color::new(1.0, 0.5, 0.0, frame.thrust * GHOST_ALPHA)),
+            );
+        }
+        Ok(())
+    }
+
+    fn draw_at(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        position: Point2<f32>,
+        angle: f32,
+        theme: &Theme,
+    ) -> GameResult {
+        let meshes = self.meshes(ctx)?;
+        let param = DrawParam::default().dest(position).rotation(angle);
+
+        // Glow added on top of whatever is behind, brighter with more thrust;
+        // then the flame, whose base the body covers
+        if self.thrust > 0.0 {
+            let blend = canvas.blend_mode();
+            canvas.set_blend_mode(BlendMode::ADD);
+            let glow = Color {
+                a: GLOW_ALPHA * self.thrust,
+                ..theme.flame
+            };
+            canvas.draw(&meshes.glow, param.color(glow));
+            canvas.set_blend_mode(blend);
+
+            let mut flame = theme.flame;
+            flame.a = self.thrust;
+            canvas.draw(&meshes.flame, param.color(flame));
+        }
 
-        // Draw main body
-        mb.polygon(DrawMode::fill(), &points, Color::WHITE)?;
+        // Draw lander body
+        canvas.draw(&meshes.body, param.color(theme.lander_body));
+        canvas.draw(&meshes.nozzle, param.color(theme.lander_body));
+        canvas.draw(&meshes.outline, param.color(theme.lander_body));
+
+        // Draw RCS side flame if firing; it exits opposite the push direction
+        if self.rcs != 0.0 {
+            let side = -self.rcs.signum();
+            canvas.draw(
+                &meshes.rcs_flame,
+                param
+                    .scale([side, 1.0])
+                    .color(Color::new(1.0, 1.0, 1.0, self.rcs.abs())),
+            );
+        }
+
+        Ok(())
+    }
 
-        // Draw legs
-        mb.line(&[legs[0], points[1]], 2.0, Color::WHITE)?;
-        mb.line(&[legs[1], points[2]], 2.0, Color::WHITE)?;
+    fn meshes(&self, ctx: &mut Context) -> GameResult<&LanderMeshes> {
+        if let Some(meshes) = self.meshes.get() {
+            return Ok(meshes);
+        }
+        let meshes = LanderMeshes::new(ctx)?;
+        Ok(self.meshes.get_or_init(|| meshes))
+    }
 
-        Ok(Mesh::from_data(ctx, mb.build()))
+    /// Rotates a local-space point by `angle` and moves it to `position`.
+    /// Screen y points down, so a positive angle turns clockwise: at π/2,
+    /// local (1, 0) lands one pixel below `position` and (0, 1) one to its
+    /// left.
+    fn to_world(&self, local: [f32; 2]) -> Point2<f32> {
+        let (sin_angle, cos_angle) = self.angle.sin_cos();
+        Point2 {
+            x: self.position.x + (local[0] * cos_angle - local[1] * sin_angle),
+            y: self.position.y + (local[0] * sin_angle + local[1] * cos_angle),
+        }
     }
 
-    fn create_flame_mesh(&self, ctx: &mut Context) -> GameResult<Mesh> {
-        let flame_points = self.get_flame_vertices();
+    /// Center of the engine nozzle and the exhaust direction, in screen space.
+    pub fn nozzle(&self) -> (Point2<f32>, Vec2) {
+        // Same direction the flame points: local -y, rotated
+        let (sin_angle, cos_angle) = self.angle.sin_cos();
+        let direction = Vec2::new(sin_angle, -cos_angle);
+        (self.to_world(NOZZLE), direction)
+    }
+
+    pub fn draw_velocity_vector(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        // Velocity is world space (y up); flip y for the screen
+        let direction = Vec2::new(self.velocity.x, -self.velocity.y);
+        let length = (direction.length() * VELOCITY_ARROW_SCALE).min(MAX_VELOCITY_ARROW_LENGTH);
+        let color = if self.velocity.length() <= self.physics.max_safe_landing_velocity {
+            Color::GREEN
+        } else {
+            Color::RED
+        };
+
+        let from = Vec2::new(self.position.x, self.position.y);
+        let to = from + direction.normalize_or_zero() * length;
+        draw_arrow(ctx, canvas, from, to, color)
+    }
+
+    /// Gravity, thrust, wind and their sum as arrows from the lander's
+    /// center, each labeled and scaled by the acceleration it gives.
+    pub fn draw_force_arrows(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        // World space (y up), as in update()
+        let gravity = Vec2::new(0.0, -self.physics.gravity);
+        let thrust = if self.has_fuel() {
+            Vec2::new(-self.angle.cos(), self.angle.sin())
+                * self.commanded_thrust().min(1.0)
+                * self.thrust_power()
+        } else {
+            Vec2::ZERO
+        };
+        let wind = Vec2::new(self.wind, 0.0);
+        let forces = [
+            ("g", gravity, Color::new(1.0, 0.6, 0.2, 1.0)),
+            ("thrust", thrust, Color::CYAN),
+            ("wind", wind, Color::new(0.7, 0.7, 1.0, 1.0)),
+            ("net", gravity + thrust + wind, Color::YELLOW),
+        ];
 
+        let from = Vec2::new(self.position.x, self.position.y);
+        for (label, acceleration, color) in forces {
+            if acceleration.length() * FORCE_ARROW_SCALE < 1.0 {
+                continue;
+            }
+            // Flip y for the screen
+            let to = from + Vec2::new(acceleration.x, -acceleration.y) * FORCE_ARROW_SCALE;
+            draw_arrow(ctx, canvas, from, to, color)?;
+            let text = graphics::Text::new(
+                graphics::TextFragment::new(format!("{} {:.2}", label, acceleration.length()))
+                    .scale(graphics::PxScale::from(12.0)),
+            );
+            canvas.draw(
+                &text,
+                DrawParam::default()
+                    .dest(to + Vec2::new(4.0, -6.0))
+                    .color(color),
+            );
+        }
+        Ok(())
+    }
+
+    /// Where the lander would meet the ground if it coasted from here with
+    /// the engines off. `None` if it stays airborne for `MAX_PREDICTION_STEPS`.
+    pub fn predict_landing(&self, terrain: &TerrainModel) -> Option<Point2<f32>> {
+        let dt = self.physics.dt;
+        let mut position = self.position;
+        let mut velocity = self.velocity;
+        for _ in 0..MAX_PREDICTION_STEPS {
+            velocity.y -= self.physics.gravity * dt;
+            if self.drag > 0.0 {
+                let speed = velocity.length();
+                velocity -= velocity * (self.drag * speed * dt).min(1.0);
+            }
+            position.x += velocity.x * dt;
+            position.y -= velocity.y * dt;
+            position.x = match self.edge_mode {
+                EdgeMode::Clamp => position.x.clamp(0.0, WORLD_WIDTH),
+                EdgeMode::Wrap => wrap_x(position.x),
+            };
+
+            let ground = terrain.height_at(position.x);
+            if position.y >= ground {
+                return Some(Point2 {
+                    x: position.x,
+                    y: ground,
+                });
+            }
+        }
+        None
+    }
+
+    /// Marks the predicted touchdown point with a small X.
+    pub fn draw_landing_reticle(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        terrain: &TerrainModel,
+    ) -> GameResult {
+        let Some(target) = self.predict_landing(terrain) else {
+            return Ok(());
+        };
         let mut mb = MeshBuilder::new();
-        mb.polygon(
-            DrawMode::fill(),
-            &flame_points,
-            Color::new(1.0, 0.5, 0.0, self.thrust),
-        )?;
+        for (dx, dy) in [(1.0, 1.0), (1.0, -1.0)] {
+            mb.line(
+                &[
+                    Point2 {
+                        x: target.x - dx * RETICLE_SIZE,
+                        y: target.y - dy * RETICLE_SIZE,
+                    },
+                    Point2 {
+                        x: target.x + dx * RETICLE_SIZE,
+                        y: target.y + dy * RETICLE_SIZE,
+                    },
+                ],
+                2.0,
+                Color::YELLOW,
+            )?;
+        }
+        canvas.draw(&Mesh::from_data(ctx, mb.build()), DrawParam::default());
+        Ok(())
+    }
 
-        Ok(Mesh::from_data(ctx, mb.build()))
-    }
-
-    fn get_vertices(&self) -> Vec<Point2<f32>> {
-        let cos_angle = self.angle.cos();
-        let sin_angle = self.angle.sin();
-
-        vec![
-            Point2 {
-                // Nose
-                x: self.position.x + (0.0 * cos_angle - 15.0 * sin_angle),
-                y: self.position.y + (0.0 * sin_angle + 15.0 * cos_angle),
-            },
-            Point2 {
-                // Left side
-                x: self.position.x + (-10.0 * cos_angle - (-10.0) * sin_angle),
-                y: self.position.y + (-10.0 * sin_angle + (-10.0) * cos_angle),
-            },
-            Point2 {
-                // Right side
-                x: self.position.x + (10.0 * cos_angle - (-10.0) * sin_angle),
-                y: self.position.y + (10.0 * sin_angle + (-10.0) * cos_angle),
-            },
-        ]
-    }
-
-    fn get_flame_vertices(&self) -> Vec<Point2<f32>> {
-        let cos_angle = self.angle.cos();
-        let sin_angle = self.angle.sin();
-
-        vec![
-            Point2 {
-                x: self.position.x + (-5.0 * cos_angle - (-8.0) * sin_angle),
-                y: self.position.y + (-5.0 * sin_angle + (-8.0) * cos_angle),
-            },
-            Point2 {
-                x: self.position.x + (5.0 * cos_angle - (-8.0) * sin_angle),
-                y: self.position.y + (5.0 * sin_angle + (-8.0) * cos_angle),
-            },
-            Point2 {
-                x: self.position.x + (0.0 * cos_angle - (-20.0) * sin_angle),
-                y: self.position.y + (0.0 * sin_angle + (-20.0) * cos_angle),
-            },
-        ]
+    pub fn get_vertices(&self) -> Vec<Point2<f32>> {
+        BODY_VERTICES.iter().map(|&p| self.to_world(p)).collect()
     }
 
     pub fn get_legs_points(&self) -> Vec<Point2<f32>> {
-        let cos_angle = self.angle.cos();
-        let sin_angle = self.angle.sin();
+        LEG_POINTS.iter().map(|&p| self.to_world(p)).collect()
+    }
 
-        vec![
-            Point2 {
-                x: self.position.x + (-15.0 * cos_angle - (-5.0) * sin_angle),
-                y: self.position.y + (-15.0 * sin_angle + (-5.0) * cos_angle),
-            },
-            Point2 {
-                x: self.position.x + (15.0 * cos_angle - (-5.0) * sin_angle),
-                y: self.position.y + (15.0 * sin_angle + (-5.0) * cos_angle),
-            },
-        ]
+    /// Leg positions at the start of the last `update`, for swept collision.
+    pub fn previous_legs_points(&self) -> &[Point2<f32>] {
+        &self.previous_legs
     }
 
     pub fn apply_thrust(&mut self, amount: f32) {
-        self.thrust = if self.fuel > 0.0 {
+        self.thrust = if self.has_fuel() {
             let thrust = amount.clamp(0.0, 1.0);
             info!("Applying thrust: {}", thrust); // Debug log
             thrust
@@ -175,22 +760,666 @@ impl LunarLander {
         };
     }
 
+    /// Player throttle, shaped by the thrust curve. Autopilot and assist
+    /// command the engine directly with `apply_thrust`.
+    pub fn set_thrust_input(&mut self, input: f32) {
+        if input <= 0.0 {
+            self.thrust_held = 0.0;
+        }
+        self.thrust_input = input.clamp(0.0, 1.0);
+        self.apply_thrust(self.thrust_curve.shape(self.thrust_input, self.thrust_held));
+    }
+
+    pub fn set_thrust_curve(&mut self, curve: ThrustCurve) {
+        self.thrust_curve = curve;
+    }
+
+    pub fn set_drag(&mut self, drag: f32) {
+        self.drag = drag.max(0.0);
+    }
+
+    pub fn apply_rcs(&mut self, direction: f32) {
+        self.rcs = if self.has_fuel() {
+            direction.clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+    }
+
+    /// One tick of a simple landing assist, driving the same controls a
+    /// player would. It holds position over the nearest pad with RCS, points
+    /// thrust straight up (angle π/2 in this model) for a descent that slows
+    /// with height, then levels out to the surface just before touchdown.
+    pub fn autopilot_step(&mut self, terrain: &TerrainModel) {
+        let x = self.position.x;
+        let target_x = terrain
+            .nearest_pad(x)
+            .map_or(x, |pad| terrain.pad_center_x(pad));
+        let altitude = terrain.height_at(x) - self.position.y;
+
+        // Close on the pad, easing off as it gets near
+        let wanted_vx = ((target_x - x) * 0.05).clamp(-AUTOPILOT_MAX_DRIFT, AUTOPILOT_MAX_DRIFT);
+        let vx_error = wanted_vx - self.velocity.x;
+        self.apply_rcs(if vx_error.abs() > 0.1 {
+            vx_error.signum()
+        } else {
+            0.0
+        });
+
+        let flaring = altitude < AUTOPILOT_FLARE_ALTITUDE && (target_x - x).abs() < 20.0;
+        let target_angle = if flaring {
+            0.0
+        } else {
+            std::f32::consts::FRAC_PI_2
+        };
+        self.turn_toward(target_angle);
+
+        // Descend quickly when high and gently near the ground
+        let wanted_vy = -(altitude * 0.12).clamp(0.5, 12.0);
+        let accel = (wanted_vy - self.velocity.y) * 2.0 + self.physics.gravity;
+        let lift = self.angle.sin() * self.physics.thrust_power;
+        self.apply_thrust(if lift > 0.1 { accel / lift } else { 0.0 });
+    }
+
+    /// Rotates a tick's worth toward `target_angle`, the short way round.
+    pub fn turn_toward(&mut self, target_angle: f32) {
+        // Count rotation already queued this tick, in case of a second caller
+        let (angle, spin) = if self.instant_rotation {
+            (self.angle + self.queued_rotation, self.angular_velocity)
+        } else {
+            (
+                self.angle,
+                self.angular_velocity + self.queued_rotation * ANGULAR_IMPULSE,
+            )
+        };
+        let angle_error = (target_angle - angle + std::f32::consts::PI)
+            .rem_euclid(2.0 * std::f32::consts::PI)
+            - std::f32::consts::PI;
+        if self.instant_rotation {
+            self.rotate(angle_error.clamp(-0.05, 0.05));
+        } else {
+            // Steer the spin toward a rate proportional to the error
+            let wanted_spin = (angle_error * 3.0).clamp(-1.5, 1.5);
+            self.rotate((wanted_spin - spin) / ANGULAR_IMPULSE);
+        }
+    }
+
+    /// The angle at which thrust pushes toward `target`, in screen space.
+    pub fn angle_toward(&self, target: Point2<f32>) -> f32 {
+        // Thrust is (-cos, sin) in world space, where y is up
+        let dx = target.x - self.position.x;
+        let dy = self.position.y - target.y;
+        dy.atan2(-dx)
+    }
+
+    pub fn set_instant_rotation(&mut self, instant: bool) {
+        self.instant_rotation = instant;
+        if instant {
+            self.angular_velocity = 0.0;
+        }
+    }
+
+    pub fn set_assist(&mut self, assist: bool) {
+        self.assist = assist;
+    }
+
+    /// Engine power, raised while a boost lasts.
+    fn thrust_power(&self) -> f32 {
+        self.engine_power(self.boost_left > 0.0)
+    }
+
+    fn engine_power(&self, boosted: bool) -> f32 {
+        if boosted {
+            self.physics.thrust_power * BOOST_MULTIPLIER
+        } else {
+            self.physics.thrust_power
+        }
+    }
+
+    /// Throttle the engine is asked for. Manual thrust overrides the hover
+    /// assist.
+    fn commanded_thrust(&self) -> f32 {
+        if self.thrust > 0.0 {
+            self.thrust
+        } else if self.assist {
+            self.hover_thrust()
+        } else {
+            0.0
+        }
+    }
+
+    /// Throttle whose vertical component cancels gravity at the current tilt.
+    fn hover_thrust(&self) -> f32 {
+        let lift = self.angle.sin() * self.thrust_power();
+        if lift > MIN_ASSIST_LIFT {
+            (self.physics.gravity / lift).min(1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Keeps the last `length` positions as a trail; 0 turns it off.
+    pub fn set_trail_length(&mut self, length: usize) {
+        self.trail_length = length;
+        while self.trail.len() > length {
+            self.trail.pop_front();
+        }
+    }
+
+    pub fn set_edge_mode(&mut self, mode: EdgeMode) {
+        self.edge_mode = mode;
+    }
+
+    pub fn set_ceiling_mode(&mut self, mode: CeilingMode) {
+        self.ceiling_mode = mode;
+    }
+
+    /// Queues a turn for the next `update`.
     pub fn rotate(&mut self, amount: f32) {
-        self.angle = (self.angle + amount) % (2.0 * std::f32::consts::PI);
+        self.queued_rotation += amount;
+    }
+
+    fn spin(&mut self, amount: f32) {
+        if self.instant_rotation {
+            self.angle = (self.angle + amount) % (2.0 * std::f32::consts::PI);
+        } else {
+            // Torque impulse; step() integrates it into the angle
+            self.angular_velocity += amount * ANGULAR_IMPULSE;
+        }
     }
 
-    pub fn check_landing_safety(&mut self, surface_angle: f32) {
+    /// Judges the touchdown once. A gentle contact off a pad still survives,
+    /// but only counts as a rough landing.
+    pub fn check_landing_safety(&mut self, surface_angle: f32, on_pad: bool) {
         if !self.landing_safety_checked {
             let velocity_magnitude = self.velocity.length();
             let relative_angle = (self.angle - surface_angle).abs();
 
-            self.landed_safely = velocity_magnitude <= MAX_SAFE_LANDING_VELOCITY
-                && relative_angle <= MAX_SAFE_LANDING_ANGLE;
+            self.landing_angle_error = relative_angle;
+            self.landed_safely = velocity_magnitude <= self.physics.max_safe_landing_velocity
+                && relative_angle <= self.physics.max_safe_landing_angle
+                && self.angular_velocity.abs() <= MAX_SAFE_ANGULAR_VELOCITY;
+            self.landed_on_pad = on_pad;
             self.landing_safety_checked = true;
         }
     }
 
+    /// Judges a contact made by the body rather than the legs. The nose hangs
+    /// below the legs at the landing attitude, so only a tipped-over lander
+    /// crashes here; otherwise the legs get to decide. Returns whether it crashed.
+    pub fn check_body_impact(&mut self, surface_angle: f32) -> bool {
+        if self.landing_safety_checked {
+            return !self.landed_safely;
+        }
+        let relative_angle = (self.angle - surface_angle).abs();
+        if relative_angle <= self.physics.max_safe_landing_angle {
+            return false;
+        }
+        self.landing_angle_error = relative_angle;
+        self.crash();
+        true
+    }
+
+    /// Crashes the lander if it has dropped to the bottom of the screen, the
+    /// hard floor under any gap in the terrain. Returns whether it did.
+    pub fn check_floor(&mut self) -> bool {
+        if self.position.y < SCREEN_HEIGHT {
+            return false;
+        }
+        self.crash();
+        true
+    }
+
+    /// Latches the landing as a crash, for contacts that can never be landed on.
+    pub fn crash(&mut self) {
+        self.landed_safely = false;
+        self.landed_on_pad = false;
+        self.landing_safety_checked = true;
+    }
+
+    /// Brings the lander to rest with its lowest leg just above `surface_y`.
+    pub fn settle(&mut self, surface_y: f32) {
+        self.velocity = Vec2::ZERO;
+        self.angular_velocity = 0.0;
+        let lowest = self
+            .get_legs_points()
+            .iter()
+            .map(|leg| leg.y)
+            .fold(f32::MIN, f32::max);
+        self.position.y -= lowest - surface_y + 0.5;
+    }
+
+    /// Clears the landing latch so the lander can take off and land again.
+    pub fn liftoff(&mut self) {
+        self.landing_safety_checked = false;
+        self.landed_safely = false;
+        self.landed_on_pad = false;
+    }
+
+    /// Multiplies thrust power for the next `seconds`, replacing any boost
+    /// already running.
+    pub fn boost(&mut self, seconds: f32) {
+        self.boost_left = seconds;
+    }
+
+    /// Seconds of boosted thrust left; 0 when not boosted.
+    pub fn boost_left(&self) -> f32 {
+        self.boost_left
+    }
+
+    /// The single gate for firing the engines; `update` keeps fuel >= 0.
+    fn has_fuel(&self) -> bool {
+        self.fuel > 0.0
+    }
+
+    /// Whether the latest update burned the last of the fuel. True for that
+    /// one tick only, so the flameout can be cued once.
+    pub fn ran_dry(&self) -> bool {
+        self.ran_dry
+    }
+
+    /// Remaining fuel as a fraction of the tank, 0..1.
+    pub fn fuel_fraction(&self) -> f32 {
+        (self.fuel / self.max_fuel).clamp(0.0, 1.0)
+    }
+
+    /// Whether the current touchdown has been judged yet.
+    pub fn is_landing_checked(&self) -> bool {
+        self.landing_safety_checked
+    }
+
     pub fn is_landed_safely(&self) -> bool {
         self.landed_safely
     }
+
+    /// A safe landing on a pad, as opposed to a rough one on open ground.
+    pub fn is_pad_landing(&self) -> bool {
+        self.landed_safely && self.landed_on_pad
+    }
+
+    /// Tilt relative to the surface at touchdown, in radians.
+    pub fn landing_angle_error(&self) -> f32 {
+        self.landing_angle_error
+    }
+}
+
+/// Fuel for the cheapest vertical descent from rest `height` px up to rest
+/// on the ground: free fall, then one full-thrust burn timed to stop exactly
+/// at the surface. With gravity g and thrust acceleration T, the peak speed
+/// is v = sqrt(2·h·g·(T - g) / T), the burn lasts v / (T - g), and the
+/// engine uses `THRUST_FUEL_RATE` per second of it. Sideways travel and the
+/// safe touchdown speed are ignored, so real landings always use more.
+pub fn ideal_descent_fuel(height: f32, physics: &PhysicsConfig) -> f32 {
+    let PhysicsConfig {
+        gravity,
+        thrust_power,
+        ..
+    } = *physics;
+    if height <= 0.0 || thrust_power <= gravity {
+        return 0.0;
+    }
+    let braking = thrust_power - gravity;
+    let peak_speed = (2.0 * height * gravity * braking / thrust_power).sqrt();
+    peak_speed / braking * THRUST_FUEL_RATE
+}
+
+/// Wraps `x` into `0..WORLD_WIDTH`.
+fn wrap_x(x: f32) -> f32 {
+    x.rem_euclid(WORLD_WIDTH)
+}
+
+/// Draws a line from `from` to `to` with a small arrowhead, in screen space.
+pub fn draw_arrow(
+    ctx: &mut Context,
+    canvas: &mut Canvas,
+    from: Vec2,
+    to: Vec2,
+    color: Color,
+) -> GameResult {
+    let shaft = to - from;
+    if shaft.length() < 1.0 {
+        return Ok(());
+    }
+
+    let head = shaft.normalize() * 8.0_f32.min(shaft.length());
+    let left = to - head + head.perp() * 0.5;
+    let right = to - head - head.perp() * 0.5;
+
+    let mut mb = MeshBuilder::new();
+    mb.line(&[from, to], 2.0, color)?;
+    mb.line(&[left, to, right], 2.0, color)?;
+
+    canvas.draw(
+        &Mesh::from_data(ctx, mb.build()),
+        graphics::DrawParam::default(),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lander() -> LunarLander {
+        LunarLander::with_physics(100.0, 200.0, PhysicsConfig::lunar())
+    }
+
+    fn assert_points(actual: &[Point2<f32>], expected: &[[f32; 2]]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(
+                (a.x - e[0]).abs() < 1e-4 && (a.y - e[1]).abs() < 1e-4,
+                "{:?} != {:?}",
+                (a.x, a.y),
+                e
+            );
+        }
+    }
+
+    #[test]
+    fn vertices_at_angle_zero_are_local_geometry_moved_to_position() {
+        let lander = lander();
+        assert_points(
+            &lander.get_vertices(),
+            &[[100.0, 216.666_67], [90.0, 191.666_67], [110.0, 191.666_67]],
+        );
+        assert_points(
+            &lander.get_legs_points(),
+            &[[85.0, 196.666_67], [115.0, 196.666_67]],
+        );
+    }
+
+    #[test]
+    fn vertices_at_quarter_turn_rotate_clockwise_on_screen() {
+        let mut lander = lander();
+        lander.angle = std::f32::consts::FRAC_PI_2;
+        assert_points(
+            &lander.get_vertices(),
+            &[[83.333_33, 200.0], [108.333_33, 190.0], [108.333_33, 210.0]],
+        );
+        assert_points(
+            &lander.get_legs_points(),
+            &[[103.333_33, 185.0], [103.333_33, 215.0]],
+        );
+    }
+
+    /// A short flight: burn up and right, coast, then a left correction.
+    fn script() -> Vec<LanderInput> {
+        (0..240)
+            .map(|tick| LanderInput {
+                thrust: if tick < 90 { 0.8 } else { 0.0 },
+                rotation: if tick < 20 { 0.01 } else { 0.0 },
+                rcs: if tick >= 180 { -1.0 } else { 0.0 },
+                wind: 0.3,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    fn fly(inputs: &[LanderInput]) -> LunarLander {
+        let mut lander = lander();
+        let PhysicsConfig { gravity, dt, .. } = lander.physics;
+        for &input in inputs {
+            lander.step(input, dt, gravity);
+        }
+        lander
+    }
+
+    #[test]
+    fn step_replays_a_fixed_input_sequence_exactly() {
+        let (a, b) = (fly(&script()), fly(&script()));
+        assert_eq!(a.position.x.to_bits(), b.position.x.to_bits());
+        assert_eq!(a.position.y.to_bits(), b.position.y.to_bits());
+        assert_eq!(a.velocity, b.velocity);
+        assert_eq!(a.angle.to_bits(), b.angle.to_bits());
+        assert_eq!(a.fuel.to_bits(), b.fuel.to_bits());
+
+        // Every control in the script took effect
+        assert!(a.fuel < 100.0);
+        assert!(a.angle > 0.0);
+        assert!(a.velocity.x != 0.0 && a.velocity.y != 0.0);
+    }
+
+    #[test]
+    fn update_runs_the_held_controls_through_step() {
+        let mut held = lander();
+        let PhysicsConfig { gravity, dt, .. } = held.physics;
+        let mut stepped = lander();
+        for input in script() {
+            held.apply_thrust(input.thrust);
+            held.rotate(input.rotation);
+            held.apply_rcs(input.rcs);
+            held.wind = input.wind;
+            held.update();
+            stepped.step(input, dt, gravity);
+        }
+        assert_eq!(held.position.x, stepped.position.x);
+        assert_eq!(held.position.y, stepped.position.y);
+        assert_eq!(held.velocity, stepped.velocity);
+        assert_eq!(held.angle, stepped.angle);
+        assert_eq!(held.fuel, stepped.fuel);
+    }
+
+    #[test]
+    fn step_ignores_controls_it_is_not_given() {
+        let mut lander = lander();
+        let PhysicsConfig { gravity, dt, .. } = lander.physics;
+        lander.apply_thrust(1.0);
+        lander.apply_rcs(1.0);
+        lander.wind = 5.0;
+        lander.step(LanderInput::default(), dt, gravity);
+        assert_eq!(lander.velocity.x, 0.0);
+        assert_eq!(lander.fuel, 100.0);
+    }
+
+    /// Full thrust straight up for two minutes, with fuel to spare.
+    /// Returns the lander and the highest it got (lowest screen y).
+    fn climb(mode: CeilingMode) -> (LunarLander, f32) {
+        let mut lander = lander();
+        lander.angle = std::f32::consts::FRAC_PI_2;
+        lander.fuel = f32::MAX;
+        lander.set_ceiling_mode(mode);
+        let mut highest = lander.position.y;
+        for _ in 0..7200 {
+            lander.apply_thrust(1.0);
+            lander.update();
+            highest = highest.min(lander.position.y);
+        }
+        (lander, highest)
+    }
+
+    #[test]
+    fn long_climb_stops_at_the_top_of_the_screen() {
+        let (lander, highest) = climb(CeilingMode::Clamp);
+        assert_eq!(highest, 0.0);
+        assert_eq!(lander.position.y, 0.0);
+        assert!(lander.velocity.y <= 0.0);
+    }
+
+    #[test]
+    fn long_climb_in_nudge_mode_never_strays_far_above_the_top() {
+        // Arriving at ~27 m/s against a 0.05 /s² spring and 1.88 m/s² of net
+        // lift, it swings out to about 166 px above the top and back
+        let (_, highest) = climb(CeilingMode::Nudge);
+        assert!(highest < 0.0);
+        assert!(highest > -200.0, "{}", highest);
+    }
+
+    #[test]
+    fn hover_assist_holds_height_while_boosted() {
+        let mut lander = lander();
+        lander.angle = std::f32::consts::FRAC_PI_2; // thrusting straight up
+        lander.set_assist(true);
+        lander.boost(1.0);
+        for _ in 0..30 {
+            lander.update();
+        }
+        assert!(lander.velocity.y.abs() < 1e-3, "{}", lander.velocity.y);
+    }
+
+    #[test]
+    fn legs_stay_symmetric_about_position_at_any_angle() {
+        let mut lander = lander();
+        for step in 0..16 {
+            lander.angle = step as f32 * std::f32::consts::TAU / 16.0;
+            let legs = lander.get_legs_points();
+            let mid_x = (legs[0].x + legs[1].x) / 2.0;
+            let mid_y = (legs[0].y + legs[1].y) / 2.0;
+            let offset = Vec2::new(mid_x - 100.0, mid_y - 200.0);
+            // Midway between the legs stays LEG_POINTS' 3⅓ px from the pivot
+            assert!((offset.length() - (5.0 - CENTROID_LIFT)).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn drag_brings_a_fall_to_terminal_velocity() {
+        let mut lander = lander();
+        lander.set_drag(0.01);
+        let gravity = lander.physics.gravity;
+        for _ in 0..3000 {
+            lander.update();
+        }
+        // Drag k·v² balances gravity at v = sqrt(g / k), less a little for
+        // applying gravity before drag within each tick
+        let terminal = (gravity / 0.01).sqrt();
+        assert!(
+            (lander.velocity.y + terminal).abs() < 0.05,
+            "{}",
+            lander.velocity.y
+        );
+        let before = lander.velocity.y;
+        lander.update();
+        assert!((lander.velocity.y - before).abs() < 1e-4);
+    }
+
+    #[test]
+    fn unpowered_lander_gains_gravity_each_tick_and_falls_down_the_screen() {
+        let mut lander = lander();
+        let PhysicsConfig { gravity, dt, .. } = lander.physics;
+        for tick in 1..=120 {
+            let y = lander.position.y;
+            lander.update();
+            let expected = -gravity * dt * tick as f32;
+            assert!((lander.velocity.y - expected).abs() < 1e-4);
+            assert!(lander.position.y > y);
+        }
+        assert_eq!(lander.velocity.x, 0.0);
+    }
+
+    /// One weightless tick drifting at `speed` px/s from `x`.
+    fn drift(mode: EdgeMode, x: f32, speed: f32) -> LunarLander {
+        let mut lander = LunarLander::with_physics(x, 200.0, PhysicsConfig::lunar());
+        lander.set_edge_mode(mode);
+        lander.velocity = Vec2::new(speed, 0.0);
+        lander.step(LanderInput::default(), 1.0 / 60.0, 0.0);
+        lander
+    }
+
+    #[test]
+    fn wrap_mode_carries_the_lander_across_both_edges() {
+        let right = drift(EdgeMode::Wrap, WORLD_WIDTH - 0.5, 60.0);
+        assert!(
+            (right.position.x - 0.5).abs() < 1e-3,
+            "{}",
+            right.position.x
+        );
+        assert_eq!(right.velocity, Vec2::new(60.0, 0.0));
+        // The leg sweep moved with it rather than spanning the field
+        assert!(right.previous_legs_points()[0].x < 0.0);
+
+        let left = drift(EdgeMode::Wrap, 0.5, -60.0);
+        assert!(
+            (left.position.x - (WORLD_WIDTH - 0.5)).abs() < 1e-3,
+            "{}",
+            left.position.x
+        );
+        assert_eq!(left.velocity, Vec2::new(-60.0, 0.0));
+    }
+
+    #[test]
+    fn clamp_mode_stops_at_the_walls() {
+        assert_eq!(
+            drift(EdgeMode::Clamp, WORLD_WIDTH - 0.5, 60.0).position.x,
+            WORLD_WIDTH
+        );
+        assert_eq!(drift(EdgeMode::Clamp, 0.5, -60.0).position.x, 0.0);
+    }
+
+    #[test]
+    fn one_second_of_full_thrust_burns_the_same_fuel_at_any_tick_rate() {
+        for ticks in [30, 60, 120, 240] {
+            let mut lander = lander();
+            let start = lander.fuel;
+            let input = LanderInput {
+                thrust: 1.0,
+                ..Default::default()
+            };
+            for _ in 0..ticks {
+                lander.step(input, 1.0 / ticks as f32, lander.physics.gravity);
+            }
+            let burned = start - lander.fuel;
+            assert!(
+                (burned - THRUST_FUEL_RATE).abs() < 1e-3,
+                "{} burned over {} ticks",
+                burned,
+                ticks
+            );
+            assert_eq!(lander.fuel_used, burned);
+        }
+    }
+
+    #[test]
+    fn last_drop_of_fuel_buys_only_the_thrust_it_pays_for() {
+        let dt = 1.0 / 60.0;
+        let input = LanderInput {
+            thrust: 1.0,
+            ..Default::default()
+        };
+        let lift = |fuel: f32| {
+            let mut lander = lander();
+            lander.angle = std::f32::consts::FRAC_PI_2;
+            lander.fuel = fuel;
+            lander.step(input, dt, 0.0);
+            lander
+        };
+
+        let full = lift(100.0);
+        assert!(!full.ran_dry());
+
+        // Half of one tick's burn left
+        let drained = lift(THRUST_FUEL_RATE * dt / 2.0);
+        assert_eq!(drained.fuel, 0.0);
+        assert!(drained.ran_dry());
+        assert!((drained.velocity.y - full.velocity.y / 2.0).abs() < 1e-5);
+
+        // Nothing left to burn on the next tick
+        let mut empty = drained;
+        let before = empty.velocity;
+        empty.step(input, dt, 0.0);
+        assert_eq!(empty.fuel, 0.0);
+        assert!(!empty.ran_dry());
+        assert_eq!(empty.velocity, before);
+    }
+
+    #[test]
+    fn thrust_curves_shape_the_input() {
+        for (input, quadratic) in [(0.0, 0.0), (0.25, 0.0625), (0.5, 0.25), (1.0, 1.0)] {
+            assert_eq!(ThrustCurve::Linear.shape(input, 0.0), input);
+            assert_eq!(ThrustCurve::Quadratic.shape(input, 0.0), quadratic);
+        }
+        assert_eq!(ThrustCurve::Ramp.shape(1.0, 0.0), 0.0);
+        assert_eq!(ThrustCurve::Ramp.shape(1.0, THRUST_RAMP_TIME / 4.0), 0.25);
+        assert_eq!(ThrustCurve::Ramp.shape(0.5, THRUST_RAMP_TIME / 2.0), 0.25);
+        assert_eq!(ThrustCurve::Ramp.shape(0.5, THRUST_RAMP_TIME * 3.0), 0.5);
+    }
+
+    #[test]
+    fn body_centroid_is_the_pivot_and_legs_mirror_about_it() {
+        let centroid = BODY_VERTICES
+            .iter()
+            .fold(Vec2::ZERO, |sum, &p| sum + Vec2::from(p))
+            / 3.0;
+        assert!(centroid.length() < 1e-5, "{:?}", centroid);
+        assert_eq!(LEG_POINTS[0], [-LEG_POINTS[1][0], LEG_POINTS[1][1]]);
+    }
 }
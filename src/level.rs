@@ -0,0 +1,133 @@
+use std::fs;
+use std::path::Path;
+
+use ggez::{GameError, GameResult};
+use serde::{Deserialize, Serialize};
+
+use crate::physics::PhysicsConfig;
+use crate::screen::SCREEN_HEIGHT;
+use crate::terrain::WORLD_WIDTH;
+
+/// A hand-made level, loaded from TOML or JSON in place of a generated one.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LevelDef {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "lunar_gravity")]
+    pub gravity: f32, // m/s²
+    #[serde(default)]
+    pub wind: f32, // steady m/s², positive to the right
+    pub fuel: f32, // tank capacity, full at spawn
+    pub spawn: [f32; 2],
+    /// Surface points (x, screen y), left to right across the whole world.
+    pub terrain: Vec<[f32; 2]>,
+    // Last, since TOML writes tables after plain values
+    pub pads: Vec<PadDef>,
+}
+
+/// A landing pad over terrain points `start..end`, which must be level.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PadDef {
+    pub start: usize,
+    pub end: usize,
+    #[serde(default = "single_multiplier")]
+    pub multiplier: u32,
+    #[serde(default)]
+    pub refuel: bool,
+}
+
+fn lunar_gravity() -> f32 {
+    PhysicsConfig::lunar().gravity
+}
+
+fn single_multiplier() -> u32 {
+    1
+}
+
+/// Reads a level from `path`, as JSON if it has a `.json` extension and TOML
+/// otherwise, and checks it can be played.
+pub fn load_level(path: &Path) -> GameResult<LevelDef> {
+    let error = |e: String| GameError::ResourceLoadError(format!("{}: {}", path.display(), e));
+    let contents = fs::read_to_string(path).map_err(|e| error(e.to_string()))?;
+    let level: LevelDef = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&contents).map_err(|e| error(e.to_string()))?
+    } else {
+        toml::from_str(&contents).map_err(|e| error(e.to_string()))?
+    };
+    level.validate().map_err(error)?;
+    Ok(level)
+}
+
+/// Writes `level` to `path`, as JSON for a `.json` extension and TOML
+/// otherwise.
+pub fn save_level(path: &Path, level: &LevelDef) -> GameResult {
+    let error = |e: String| GameError::CustomError(format!("{}: {}", path.display(), e));
+    let contents = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::to_string_pretty(level).map_err(|e| error(e.to_string()))?
+    } else {
+        toml::to_string(level).map_err(|e| error(e.to_string()))?
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| error(e.to_string()))?;
+    }
+    fs::write(path, contents).map_err(|e| error(e.to_string()))
+}
+
+impl LevelDef {
+    /// Checks the terrain covers the world left to right, every pad is a
+    /// level run of real points, at least one pad ends the level, and the
+    /// lander starts above ground.
+    pub fn validate(&self) -> Result<(), String> {
+        let points = &self.terrain;
+        if points.len() < 2 {
+            return Err("terrain needs at least two points".to_string());
+        }
+        if points[0][0] > 0.0 || points[points.len() - 1][0] < WORLD_WIDTH {
+            return Err(format!("terrain must span x = 0 to {}", WORLD_WIDTH));
+        }
+        if points.windows(2).any(|pair| pair[1][0] <= pair[0][0]) {
+            return Err("terrain x must increase left to right".to_string());
+        }
+        if points.iter().any(|p| !(0.0..SCREEN_HEIGHT).contains(&p[1])) {
+            return Err(format!("terrain y must be within 0..{}", SCREEN_HEIGHT));
+        }
+
+        for (i, pad) in self.pads.iter().enumerate() {
+            if pad.start + 2 > pad.end || pad.end > points.len() {
+                return Err(format!(
+                    "pad {} ({}..{}) must cover at least two of the {} terrain points",
+                    i,
+                    pad.start,
+                    pad.end,
+                    points.len()
+                ));
+            }
+            let height = points[pad.start][1];
+            if points[pad.start..pad.end].iter().any(|p| p[1] != height) {
+                return Err(format!("pad {} is not level", i));
+            }
+        }
+        if self.pads.iter().all(|pad| pad.refuel) {
+            return Err("at least one pad must be a landing pad, not refuel".to_string());
+        }
+
+        if self.gravity <= 0.0 || self.fuel <= 0.0 {
+            return Err("gravity and fuel must be positive".to_string());
+        }
+        let [x, y] = self.spawn;
+        if !(0.0..=WORLD_WIDTH).contains(&x) || y < 0.0 || y >= self.ground_at(x) {
+            return Err(format!("spawn ({}, {}) must be above the terrain", x, y));
+        }
+        Ok(())
+    }
+
+    /// Surface y under `x`, between the two points either side of it.
+    fn ground_at(&self, x: f32) -> f32 {
+        let points = &self.terrain;
+        let i = points
+            .partition_point(|p| p[0] < x)
+            .clamp(1, points.len() - 1);
+        let ([x0, y0], [x1, y1]) = (points[i - 1], points[i]);
+        y0 + (y1 - y0) * ((x - x0) / (x1 - x0)).clamp(0.0, 1.0)
+    }
+}
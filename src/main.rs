@@ -1,11 +1,35 @@
-use ggez::conf::{WindowMode, WindowSetup};
-use ggez::{ContextBuilder, GameResult};
+use std::path::Path;
+
+use ggez::conf::WindowSetup;
+use ggez::{ContextBuilder, GameError, GameResult};
 
 use log::debug;
+mod attract;
+mod audio;
+mod camera;
+mod config;
+mod console;
+mod daily;
+mod difficulty;
+mod editor;
 mod game;
+mod ghost;
+mod highscore;
+mod keybindings;
 mod lander;
+mod level;
+mod meteor;
 mod particles;
+mod physics;
+mod powerup;
+mod replay;
+mod screen;
+mod settings;
+mod telemetry;
 mod terrain;
+mod theme;
+mod timestep;
+mod wind;
 
 fn main() -> GameResult {
     // Initialize logger
@@ -14,17 +38,37 @@ fn main() -> GameResult {
     // Your existing ggez setup
     debug!("Starting game...");
 
-    let window_setup = WindowSetup::default().title("Lunar Lander").vsync(true);
+    // --level <file> plays a hand-made level instead of generated ones
+    let args: Vec<String> = std::env::args().collect();
+    let custom_level = match args.iter().position(|arg| arg == "--level") {
+        Some(i) => {
+            let Some(path) = args.get(i + 1) else {
+                return Err(GameError::CustomError(
+                    "--level needs a level file".to_string(),
+                ));
+            };
+            Some(level::load_level(Path::new(path))?)
+        }
+        None => None,
+    };
+
+    // The developer console is always there in debug builds
+    let console = cfg!(debug_assertions) || args.iter().any(|arg| arg == "--console");
+
+    // vsync can only be chosen when the window is created
+    let settings = settings::Settings::load();
+    let window_setup = WindowSetup::default()
+        .title("Lunar Lander")
+        .vsync(settings.vsync);
 
-    let window_mode = WindowMode::default()
-        .dimensions(800.0, 600.0)
-        .resizable(false);
+    let window_mode = settings.window_mode();
 
     let (mut ctx, event_loop) = ContextBuilder::new("Lunar Lander", "Christopher Brown")
         .window_setup(window_setup)
         .window_mode(window_mode)
+        .resources_dir_name("assets")
         .build()?;
 
-    let game_state = game::MainState::new(&mut ctx)?;
+    let game_state = game::MainState::new(&mut ctx, custom_level, console)?;
     ggez::event::run(ctx, event_loop, game_state)
 }
@@ -0,0 +1,177 @@
+use ggez::graphics::{Canvas, Color, DrawMode, DrawParam, Mesh, MeshBuilder};
+use ggez::mint::Point2;
+use ggez::{Context, GameResult};
+use glam::Vec2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::lander::LunarLander;
+use crate::particles::{ExhaustEmitter, Explosion, ExplosionPalette};
+use crate::terrain::{TerrainModel, WORLD_WIDTH};
+
+const METEORS_FROM_LEVEL: u32 = 5;
+const SPAWN_INTERVAL: (f32, f32) = (1.5, 4.0); // seconds between meteors
+const METEOR_SPEED: (f32, f32) = (120.0, 220.0); // px/s
+const MAX_DRIFT: f32 = 0.5; // sideways px per px fallen
+const METEOR_RADIUS: f32 = 4.0;
+const IMPACT_INTENSITY: f32 = 0.3;
+const TAIL_INTENSITY: f32 = 0.3;
+
+/// A falling rock, in screen space.
+struct Meteor {
+    position: Vec2,
+    previous: Vec2,
+    velocity: Vec2, // px/s, screen y down
+}
+
+/// Meteors raining in from the top of the world on later levels. They crash
+/// the lander on contact and burst when they hit the ground.
+pub struct MeteorShower {
+    enabled: bool,
+    meteors: Vec<Meteor>,
+    next_spawn: f32,
+    rng: StdRng,
+    tail: ExhaustEmitter,
+    impacts: Vec<Explosion>,
+}
+
+impl MeteorShower {
+    /// Seeded from the level so replays see the same meteors.
+    pub fn for_level(seed: u64, level: u32, gravity: f32) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let next_spawn = rng.gen_range(SPAWN_INTERVAL.0..SPAWN_INTERVAL.1);
+        MeteorShower {
+            enabled: level >= METEORS_FROM_LEVEL,
+            meteors: Vec::new(),
+            next_spawn,
+            rng,
+            tail: ExhaustEmitter::new(gravity),
+            impacts: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, dt: f32, terrain: &TerrainModel, gravity: f32) {
+        if self.enabled {
+            self.next_spawn -= dt;
+            if self.next_spawn <= 0.0 {
+                self.next_spawn = self.rng.gen_range(SPAWN_INTERVAL.0..SPAWN_INTERVAL.1);
+                let x = self.rng.gen_range(0.0..WORLD_WIDTH);
+                let speed = self.rng.gen_range(METEOR_SPEED.0..METEOR_SPEED.1);
+                let drift = self.rng.gen_range(-MAX_DRIFT..MAX_DRIFT);
+                let position = Vec2::new(x, -METEOR_RADIUS);
+                self.meteors.push(Meteor {
+                    position,
+                    previous: position,
+                    velocity: Vec2::new(drift, 1.0).normalize() * speed,
+                });
+            }
+        }
+
+        for meteor in &mut self.meteors {
+            meteor.previous = meteor.position;
+            meteor.position += meteor.velocity * dt;
+            // Tail streams out behind
+            self.tail.emit(
+                &mut self.rng,
+                Point2::from(meteor.position.to_array()),
+                -meteor.velocity.normalize(),
+                TAIL_INTENSITY,
+            );
+        }
+
+        // Burst on the ground
+        let rng = &mut self.rng;
+        let impacts = &mut self.impacts;
+        self.meteors.retain(|meteor| {
+            let ground = terrain.height_at(meteor.position.x);
+            if meteor.position.y < ground {
+                return true;
+            }
+            impacts.push(Explosion::with_intensity(
+                rng,
+                meteor.position.x,
+                ground,
+                gravity,
+                IMPACT_INTENSITY,
+                ExplosionPalette::PLASMA,
+            ));
+            false
+        });
+
+        self.tail.update();
+        for impact in &mut self.impacts {
+            impact.update();
+        }
+        self.impacts.retain(|impact| !impact.is_finished());
+    }
+
+    /// Whether any meteor passed through the lander this tick: within
+    /// `METEOR_RADIUS` of a body vertex or leg along its swept path, or
+    /// inside the body.
+    pub fn hits(&self, lander: &LunarLander) -> bool {
+        let body = lander.get_vertices();
+        let points: Vec<Vec2> = body
+            .iter()
+            .chain(&lander.get_legs_points())
+            .map(|&p| Vec2::from(p))
+            .collect();
+        let body: Vec<Vec2> = body.into_iter().map(Vec2::from).collect();
+        self.meteors.iter().any(|meteor| {
+            points.iter().any(|&point| {
+                distance_to_segment(point, meteor.previous, meteor.position) <= METEOR_RADIUS
+            }) || in_triangle(meteor.position, &body)
+        })
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        self.tail.draw(ctx, canvas)?;
+        for impact in &mut self.impacts {
+            impact.draw(ctx, canvas)?;
+        }
+        if self.meteors.is_empty() {
+            return Ok(());
+        }
+
+        // Glowing halo around a hot core
+        let mut mb = MeshBuilder::new();
+        for meteor in &self.meteors {
+            let center = Point2::from(meteor.position.to_array());
+            mb.circle(
+                DrawMode::fill(),
+                center,
+                METEOR_RADIUS * 2.0,
+                0.1,
+                Color::new(1.0, 0.5, 0.1, 0.3),
+            )?;
+            mb.circle(
+                DrawMode::fill(),
+                center,
+                METEOR_RADIUS,
+                0.1,
+                Color::new(1.0, 0.9, 0.6, 1.0),
+            )?;
+        }
+        canvas.draw(&Mesh::from_data(ctx, mb.build()), DrawParam::default());
+        Ok(())
+    }
+}
+
+fn distance_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_squared() > 0.0 {
+        ((point - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    point.distance(a + ab * t)
+}
+
+fn in_triangle(point: Vec2, triangle: &[Vec2]) -> bool {
+    let side = |a: Vec2, b: Vec2| (b - a).perp_dot(point - a);
+    let d1 = side(triangle[0], triangle[1]);
+    let d2 = side(triangle[1], triangle[2]);
+    let d3 = side(triangle[2], triangle[0]);
+    let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(has_negative && has_positive)
+}
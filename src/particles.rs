@@ -1,9 +1,17 @@
-use ggez::graphics::{self, Canvas, Color, DrawMode, Mesh};
+use ggez::graphics::{Canvas, Color, DrawMode, DrawParam, InstanceArray, Mesh};
 use ggez::mint::Point2;
 use ggez::{Context, GameResult};
+use glam::Vec2;
 use log::info;
 use rand::Rng;
 
+const DT: f32 = 1.0 / 60.0;
+// Debris speeds are in screen pixels, so level gravity is exaggerated to keep
+// the arc visible (lunar gravity gives roughly the old 60 px/s² pull).
+const PARTICLE_GRAVITY_SCALE: f32 = 40.0;
+const BASE_EXPLOSION_PARTICLES: f32 = 100.0;
+const MAX_EXPLOSION_PARTICLES: usize = 400;
+
 pub struct Particle {
     position: Point2<f32>,
     velocity: Point2<f32>,
@@ -12,31 +20,38 @@ pub struct Particle {
 }
 
 impl Particle {
-    fn new(x: f32, y: f32) -> Self {
-        let mut rng = rand::thread_rng();
+    fn new(rng: &mut impl Rng, x: f32, y: f32, intensity: f32) -> Self {
         let angle = rng.gen_range(0.0..std::f32::consts::PI * 2.0);
-        let speed = rng.gen_range(50.0..200.0);
-        let lifetime = rng.gen_range(0.5..1.5);
+        let speed = rng.gen_range(50.0..200.0) * intensity.sqrt();
+        let lifetime = rng.gen_range(0.5..1.5) * intensity.sqrt();
 
+        Particle::with_velocity(
+            x,
+            y,
+            Vec2::new(speed * angle.cos(), speed * angle.sin()),
+            lifetime,
+        )
+    }
+
+    fn with_velocity(x: f32, y: f32, velocity: Vec2, lifetime: f32) -> Self {
         Particle {
             position: Point2 { x, y },
             velocity: Point2 {
-                x: speed * angle.cos(),
-                y: speed * angle.sin(),
+                x: velocity.x,
+                y: velocity.y,
             },
             lifetime,
             initial_lifetime: lifetime,
         }
     }
 
-    fn update(&mut self) {
-        const DT: f32 = 1.0 / 60.0;
+    fn update(&mut self, gravity: f32) {
         self.position.x += self.velocity.x * DT;
         self.position.y += self.velocity.y * DT;
         self.lifetime -= DT;
 
-        // Add some gravity effect
-        self.velocity.y -= 1.0;
+        // Gravity pulls debris down the screen (screen y grows downward)
+        self.velocity.y += gravity * PARTICLE_GRAVITY_SCALE * DT;
     }
 
     fn is_alive(&self) -> bool {
@@ -44,20 +59,200 @@ impl Particle {
     }
 }
 
+/// A unit circle mesh plus an instance buffer, so a whole particle set is
+/// drawn with one call. Built on first draw since constructors have no context.
+#[derive(Default)]
+struct ParticleBatch {
+    batch: Option<(Mesh, InstanceArray)>,
+}
+
+impl ParticleBatch {
+    fn draw(
+        &mut self,
+        particles: &[Particle],
+        style: BurstStyle,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+    ) -> GameResult {
+        if particles.is_empty() {
+            return Ok(());
+        }
+        if self.batch.is_none() {
+            let circle = Mesh::new_circle(
+                ctx,
+                DrawMode::fill(),
+                Point2 { x: 0.0, y: 0.0 },
+                1.0,
+                0.05,
+                Color::WHITE,
+            )?;
+            self.batch = Some((circle, InstanceArray::new(ctx, None)));
+        }
+        let Some((circle, instances)) = &mut self.batch else {
+            return Ok(());
+        };
+
+        instances.set(particles.iter().map(|particle| {
+            let size = 2.0 * (particle.lifetime / particle.initial_lifetime);
+            DrawParam::default()
+                .dest(particle.position)
+                .scale([size, size])
+                .color(style.color(particle))
+        }));
+        canvas.draw_instanced_mesh(circle.clone(), instances, DrawParam::default());
+        Ok(())
+    }
+}
+
+/// The colors a fiery burst passes through as its particles age: stops of
+/// (share of life left, RGB), in rising order, blended between. A repeated
+/// share makes a hard step. Alpha fades with the life left.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExplosionPalette {
+    stops: &'static [(f32, [f32; 3])],
+}
+
+impl ExplosionPalette {
+    /// A white/yellow core, then an orange fade to red.
+    pub const CLASSIC: Self = ExplosionPalette {
+        stops: &[
+            (0.0, [1.0, 0.0, 0.0]),
+            (0.6, [1.0, 0.3, 0.0]),
+            (0.6, [1.0, 1.0, 0.8]),
+            (1.0, [1.0, 1.0, 0.8]),
+        ],
+    };
+    /// Cold blue plasma.
+    pub const PLASMA: Self = ExplosionPalette {
+        stops: &[
+            (0.0, [0.1, 0.1, 0.6]),
+            (0.5, [0.3, 0.6, 1.0]),
+            (1.0, [0.9, 0.95, 1.0]),
+        ],
+    };
+    /// Sickly green.
+    pub const TOXIC: Self = ExplosionPalette {
+        stops: &[
+            (0.0, [0.1, 0.3, 0.0]),
+            (0.5, [0.4, 0.9, 0.1]),
+            (1.0, [0.9, 1.0, 0.6]),
+        ],
+    };
+
+    fn color(self, life: f32) -> Color {
+        let stops = self.stops;
+        let i = stops
+            .partition_point(|&(share, _)| share < life)
+            .clamp(1, stops.len() - 1);
+        let ((from, a), (to, b)) = (stops[i - 1], stops[i]);
+        let t = if to > from {
+            ((life - from) / (to - from)).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        Color::new(
+            a[0] + (b[0] - a[0]) * t,
+            a[1] + (b[1] - a[1]) * t,
+            a[2] + (b[2] - a[2]) * t,
+            life,
+        )
+    }
+}
+
+impl Default for ExplosionPalette {
+    fn default() -> Self {
+        Self::CLASSIC
+    }
+}
+
+/// How a burst's particles are colored as they age.
+#[derive(Clone, Copy)]
+enum BurstStyle {
+    Fire(ExplosionPalette),
+    Dust,
+}
+
+impl BurstStyle {
+    fn color(self, particle: &Particle) -> Color {
+        let alpha = particle.lifetime / particle.initial_lifetime;
+        match self {
+            BurstStyle::Fire(palette) => palette.color(alpha),
+            // Grey/tan regolith
+            BurstStyle::Dust => Color::new(0.75, 0.7, 0.6, alpha * 0.8),
+        }
+    }
+}
+
 pub struct Explosion {
     particles: Vec<Particle>,
+    gravity: f32,
+    style: BurstStyle,
+    batch: ParticleBatch,
     notified_finished: bool,
 }
 
 impl Explosion {
-    pub fn new(x: f32, y: f32) -> Self {
+    pub fn new(
+        rng: &mut impl Rng,
+        x: f32,
+        y: f32,
+        gravity: f32,
+        palette: ExplosionPalette,
+    ) -> Self {
+        Self::with_intensity(rng, x, y, gravity, 1.0, palette)
+    }
+
+    /// Scales particle count, speed and lifetime by `intensity`, where 1.0 is
+    /// the classic 100-particle explosion. Particles are drawn from `rng`, so
+    /// the same seed gives the same burst.
+    pub fn with_intensity(
+        rng: &mut impl Rng,
+        x: f32,
+        y: f32,
+        gravity: f32,
+        intensity: f32,
+        palette: ExplosionPalette,
+    ) -> Self {
+        let intensity = intensity.clamp(0.2, 4.0);
+        let count = ((BASE_EXPLOSION_PARTICLES * intensity) as usize).min(MAX_EXPLOSION_PARTICLES);
+        let mut particles = Vec::with_capacity(count);
+        for _ in 0..count {
+            particles.push(Particle::new(rng, x, y, intensity));
+        }
+        Explosion {
+            particles,
+            gravity,
+            style: BurstStyle::Fire(palette),
+            batch: ParticleBatch::default(),
+            notified_finished: false,
+        }
+    }
+
+    /// A small, slow puff of dust that spreads sideways along the ground.
+    pub fn new_dust(rng: &mut impl Rng, x: f32, y: f32, gravity: f32) -> Self {
         let mut particles = Vec::new();
-        // Create more particles for a bigger explosion
-        for _ in 0..100 {
-            particles.push(Particle::new(x, y));
+        for _ in 0..30 {
+            // Mostly horizontal, kicked slightly up (screen y is down)
+            let lift = rng.gen_range(0.0..0.4_f32);
+            let angle = if rng.gen_bool(0.5) {
+                std::f32::consts::PI + lift
+            } else {
+                -lift
+            };
+            let speed = rng.gen_range(20.0..60.0);
+            let lifetime = rng.gen_range(0.6..1.2);
+            particles.push(Particle::with_velocity(
+                x,
+                y,
+                Vec2::new(angle.cos(), angle.sin()) * speed,
+                lifetime,
+            ));
         }
         Explosion {
             particles,
+            gravity,
+            style: BurstStyle::Dust,
+            batch: ParticleBatch::default(),
             notified_finished: false,
         }
     }
@@ -69,33 +264,130 @@ impl Explosion {
         }
         // Update all particles and remove dead ones
         for particle in &mut self.particles {
-            particle.update();
+            particle.update(self.gravity);
         }
         self.particles.retain(|p| p.is_alive());
     }
 
-    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
-        for particle in &self.particles {
-            let alpha = particle.lifetime / particle.initial_lifetime;
-            let size = 2.0 * (particle.lifetime / particle.initial_lifetime);
+    pub fn draw(&mut self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        self.batch.draw(&self.particles, self.style, ctx, canvas)
+    }
 
-            let color = if particle.lifetime > particle.initial_lifetime * 0.6 {
-                // White/yellow core
-                Color::new(1.0, 1.0, 0.8, alpha)
-            } else {
-                // Orange/red fade
-                Color::new(1.0, 0.5 * alpha, 0.0, alpha)
-            };
+    pub fn is_finished(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+}
 
-            let particle_mesh =
-                Mesh::new_circle(ctx, DrawMode::fill(), particle.position, size, 0.1, color)?;
+/// Continuous stream of short-lived exhaust particles from the main engine.
+pub struct ExhaustEmitter {
+    particles: Vec<Particle>,
+    gravity: f32,
+    batch: ParticleBatch,
+}
 
-            canvas.draw(&particle_mesh, graphics::DrawParam::default());
+impl ExhaustEmitter {
+    pub fn new(gravity: f32) -> Self {
+        ExhaustEmitter {
+            particles: Vec::new(),
+            gravity,
+            batch: ParticleBatch::default(),
         }
-        Ok(())
     }
 
-    pub fn is_finished(&self) -> bool {
-        self.particles.is_empty()
+    /// Spawns particles at `nozzle` heading along `direction` (screen space),
+    /// with more, faster particles at higher `intensity` (0..1).
+    pub fn emit(
+        &mut self,
+        rng: &mut impl Rng,
+        nozzle: Point2<f32>,
+        direction: Vec2,
+        intensity: f32,
+    ) {
+        let count = (intensity * 4.0).ceil() as usize;
+        let base_angle = direction.y.atan2(direction.x);
+
+        for _ in 0..count {
+            let angle = base_angle + rng.gen_range(-0.3..0.3);
+            let speed = rng.gen_range(60.0..120.0) * intensity;
+            let lifetime = rng.gen_range(0.2..0.5);
+            self.particles.push(Particle::with_velocity(
+                nozzle.x,
+                nozzle.y,
+                Vec2::new(angle.cos(), angle.sin()) * speed,
+                lifetime,
+            ));
+        }
+    }
+
+    pub fn update(&mut self) {
+        for particle in &mut self.particles {
+            particle.update(self.gravity);
+        }
+        self.particles.retain(|p| p.is_alive());
+    }
+
+    pub fn draw(&mut self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        self.batch.draw(
+            &self.particles,
+            BurstStyle::Fire(ExplosionPalette::CLASSIC),
+            ctx,
+            canvas,
+        )
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn particle_falls_down_the_screen() {
+        let lunar_gravity = 1.62;
+        let mut particle = Particle::with_velocity(400.0, 300.0, Vec2::ZERO, 10.0);
+        let mut last_y = particle.position.y;
+        for _ in 0..60 {
+            particle.update(lunar_gravity);
+            assert!(particle.position.y >= last_y);
+            last_y = particle.position.y;
+        }
+        assert!(particle.position.y > 300.0);
+        assert!(particle.velocity.y > 0.0);
+        assert_eq!(particle.position.x, 400.0);
+    }
+
+    fn burst(seed: u64) -> Explosion {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut explosion =
+            Explosion::with_intensity(&mut rng, 400.0, 300.0, 1.62, 2.0, ExplosionPalette::CLASSIC);
+        for _ in 0..30 {
+            explosion.update();
+        }
+        explosion
+    }
+
+    fn positions(explosion: &Explosion) -> Vec<(f32, f32)> {
+        explosion
+            .particles
+            .iter()
+            .map(|particle| (particle.position.x, particle.position.y))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_explosion() {
+        let (a, b) = (burst(5), burst(5));
+        assert!(a.particle_count() > 0);
+        assert_eq!(positions(&a), positions(&b));
+        assert_ne!(positions(&a), positions(&burst(6)));
     }
 }
@@ -0,0 +1,24 @@
+/// Physics tunables shared by a lander and the rules that judge its landing.
+#[derive(Clone, Copy, Debug)]
+pub struct PhysicsConfig {
+    pub gravity: f32, // m/s²
+    pub thrust_power: f32,
+    pub max_safe_landing_velocity: f32, // m/s
+    pub bounce_velocity_margin: f32,    // m/s over the safe limit that bounces instead of crashing
+    pub max_safe_landing_angle: f32,    // radians
+    pub dt: f32,                        // seconds per physics tick
+}
+
+impl PhysicsConfig {
+    /// The classic lunar values.
+    pub fn lunar() -> Self {
+        PhysicsConfig {
+            gravity: 1.62, // Lunar gravity (m/s²)
+            thrust_power: 3.5,
+            max_safe_landing_velocity: 2.0,
+            bounce_velocity_margin: 1.0,
+            max_safe_landing_angle: 0.15, // approximately 8.6 degrees
+            dt: 1.0 / 60.0,               // Fixed physics tick (60 Hz)
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use ggez::graphics::{Canvas, Color, DrawMode, DrawParam, Mesh, Rect};
+use ggez::{Context, GameResult};
+use glam::Vec2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::lander::LunarLander;
+use crate::terrain::{TerrainModel, WORLD_WIDTH};
+
+const SIZE: f32 = 10.0;
+const PICKUP_RADIUS: f32 = 20.0; // from the lander's center
+const HOVER_HEIGHT: (f32, f32) = (60.0, 120.0); // above the ground
+const MIN_Y: f32 = 190.0; // stays below any cave roof
+const SPIN_RATE: f32 = 2.0; // rad/s
+pub const BOOST_DURATION: f32 = 5.0; // seconds
+
+/// A floating pickup that grants a few seconds of boosted thrust. Either
+/// one-shot, or it comes back `respawn` seconds after being taken.
+#[derive(Debug, PartialEq)]
+pub struct PowerUp {
+    position: Vec2,
+    respawn: Option<f32>,
+    cooldown: f32, // seconds until it's back; 0 while available
+    taken: bool,
+    spin: f32,
+}
+
+impl PowerUp {
+    /// Places the pickup somewhere over the level, seeded from it.
+    pub fn for_level(seed: u64, terrain: &TerrainModel, respawn: Option<f32>) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let x = rng.gen_range(0.0..WORLD_WIDTH);
+        let y = (terrain.height_at(x) - rng.gen_range(HOVER_HEIGHT.0..HOVER_HEIGHT.1)).max(MIN_Y);
+        PowerUp {
+            position: Vec2::new(x, y),
+            respawn,
+            cooldown: 0.0,
+            taken: false,
+            spin: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.spin = (self.spin + SPIN_RATE * dt) % std::f32::consts::TAU;
+        if self.taken && self.respawn.is_some() {
+            self.cooldown -= dt;
+            if self.cooldown <= 0.0 {
+                self.taken = false;
+            }
+        }
+    }
+
+    /// Hands the boost to `lander` if it's flying through the pickup.
+    /// Returns whether it was collected.
+    pub fn collect(&mut self, lander: &mut LunarLander) -> bool {
+        let center = Vec2::new(lander.position.x, lander.position.y);
+        if self.taken || center.distance(self.position) > PICKUP_RADIUS {
+            return false;
+        }
+        self.taken = true;
+        self.cooldown = self.respawn.unwrap_or(0.0);
+        lander.boost(BOOST_DURATION);
+        true
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+        if self.taken {
+            return Ok(());
+        }
+        let icon = Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(-SIZE / 2.0, -SIZE / 2.0, SIZE, SIZE),
+            Color::MAGENTA,
+        )?;
+        canvas.draw(
+            &icon,
+            DrawParam::default()
+                .dest([self.position.x, self.position.y])
+                .rotation(self.spin),
+        );
+        Ok(())
+    }
+}
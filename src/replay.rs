@@ -0,0 +1,112 @@
+use std::fs;
+use std::path::Path;
+
+use ggez::input::keyboard::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::difficulty::Difficulty;
+use crate::lander::{CeilingMode, EdgeMode, ThrustCurve};
+use crate::level::LevelDef;
+
+pub const REPLAY_FILE: &str = "replay.json";
+
+/// A key press or release, stamped with the physics tick it arrived before.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct KeyEvent {
+    pub tick: u64,
+    pub key: KeyCode,
+    pub pressed: bool,
+}
+
+/// A whole session: the seed and options it started with, plus every key
+/// event. Terrain is seeded and physics runs on fixed ticks, so feeding the
+/// events back in reproduces the session exactly. Mouse and gamepad input
+/// are not recorded, so a session stops recording once either one flies the
+/// lander.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub edge_mode: EdgeMode,
+    pub instant_rotation: bool,
+    pub touch_and_go: bool,
+    pub timed: bool,
+    #[serde(default)] // missing from replays saved before hover assist
+    pub assist: bool,
+    #[serde(default)] // missing from replays saved before the autopilot was recorded
+    pub autopilot: bool,
+    #[serde(default)] // missing from replays saved before thrust curves
+    pub thrust_curve: ThrustCurve,
+    #[serde(default)] // missing from replays saved before random spawns
+    pub random_spawn: bool,
+    #[serde(default)] // missing from replays saved before the ceiling
+    pub ceiling_mode: CeilingMode,
+    #[serde(default)] // custom level played instead of seeded terrain
+    pub level: Option<LevelDef>,
+    pub events: Vec<KeyEvent>,
+}
+
+impl Replay {
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let contents = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, contents).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    }
+}
+
+/// Captures key events for the session in progress.
+pub struct ReplayRecorder {
+    replay: Replay,
+}
+
+impl ReplayRecorder {
+    /// Starts recording onto `replay`, which should have no events yet.
+    pub fn new(replay: Replay) -> Self {
+        ReplayRecorder { replay }
+    }
+
+    pub fn record(&mut self, tick: u64, key: KeyCode, pressed: bool) {
+        self.replay.events.push(KeyEvent { tick, key, pressed });
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+}
+
+/// Hands recorded key events back out as their ticks come due.
+pub struct ReplayPlayer {
+    replay: Replay,
+    next: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        ReplayPlayer { replay, next: 0 }
+    }
+
+    /// Returns the events stamped at or before `tick` not yet handed out.
+    pub fn due(&mut self, tick: u64) -> Vec<KeyEvent> {
+        let start = self.next;
+        while self
+            .replay
+            .events
+            .get(self.next)
+            .is_some_and(|event| event.tick <= tick)
+        {
+            self.next += 1;
+        }
+        self.replay.events[start..self.next].to_vec()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.replay.events.len()
+    }
+}
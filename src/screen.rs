@@ -0,0 +1,44 @@
+use ggez::graphics::Rect;
+use ggez::mint::Point2;
+
+// Logical screen size. The HUD and camera lay things out in these units and
+// the canvas scales them to whatever the window actually is.
+pub const SCREEN_WIDTH: f32 = 800.0;
+pub const SCREEN_HEIGHT: f32 = 600.0; // also the bottom of the world
+
+/// The logical-space rect that fills a `width` x `height` window while
+/// keeping the logical screen at its aspect ratio and centered. Whatever
+/// falls outside `0..SCREEN_WIDTH` x `0..SCREEN_HEIGHT` is letterboxing.
+pub fn letterbox(width: f32, height: f32) -> Rect {
+    let scale = (width / SCREEN_WIDTH).min(height / SCREEN_HEIGHT);
+    if scale <= 0.0 {
+        // Minimized
+        return Rect::new(0.0, 0.0, SCREEN_WIDTH, SCREEN_HEIGHT);
+    }
+    let w = width / scale;
+    let h = height / scale;
+    Rect::new((SCREEN_WIDTH - w) / 2.0, (SCREEN_HEIGHT - h) / 2.0, w, h)
+}
+
+/// Where a point `x`, `y` in window pixels falls in logical units, for a
+/// window `size` pixels across letterboxed by `letterbox`.
+pub fn window_to_logical(letterbox: Rect, size: (f32, f32), x: f32, y: f32) -> Point2<f32> {
+    let (width, height) = size;
+    Point2 {
+        x: letterbox.x + x / width * letterbox.w,
+        y: letterbox.y + y / height * letterbox.h,
+    }
+}
+
+/// Grows a view of the logical screen by the same margins as `letterbox`,
+/// so it maps onto the window without stretching.
+pub fn fit_view(view: Rect, letterbox: Rect) -> Rect {
+    let sx = view.w / SCREEN_WIDTH;
+    let sy = view.h / SCREEN_HEIGHT;
+    Rect::new(
+        view.x + letterbox.x * sx,
+        view.y + letterbox.y * sy,
+        letterbox.w * sx,
+        letterbox.h * sy,
+    )
+}
@@ -0,0 +1,231 @@
+use std::fs;
+
+use ggez::conf::WindowMode;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::config::config_path;
+use crate::lander::ThrustCurve;
+use crate::theme::{Theme, ThemeName};
+
+const SETTINGS_FILE: &str = "settings.toml";
+const MAX_DEADZONE: f32 = 0.9;
+
+/// Window sizes offered on the settings screen. All are 4:3 like the logical
+/// screen, which is scaled to fill the window.
+pub const RESOLUTIONS: [(f32, f32); 4] = [
+    (800.0, 600.0),
+    (1024.0, 768.0),
+    (1280.0, 960.0),
+    (1600.0, 1200.0),
+];
+
+/// How the lander is flown.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlScheme {
+    /// Keys or gamepad rotate the lander and fire the engine.
+    #[default]
+    Keyboard,
+    /// The lander turns to thrust toward the mouse cursor; the left button
+    /// fires the engine.
+    MouseAim,
+}
+
+impl ControlScheme {
+    pub fn name(self) -> &'static str {
+        match self {
+            ControlScheme::Keyboard => "Keyboard",
+            ControlScheme::MouseAim => "Mouse aim",
+        }
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            ControlScheme::Keyboard => ControlScheme::MouseAim,
+            ControlScheme::MouseAim => ControlScheme::Keyboard,
+        }
+    }
+}
+
+/// Display options, persisted to the config dir.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub vsync: bool,
+    /// Index into `RESOLUTIONS`.
+    pub resolution: usize,
+    pub thrust_curve: ThrustCurve,
+    pub theme: ThemeName,
+    /// Overrides `theme` with the high-contrast preset and its shape cues.
+    pub high_contrast: bool,
+    /// Start each level somewhere new along the top instead of the middle.
+    pub random_spawn: bool,
+    /// First-run help over the playfield, until dismissed.
+    pub show_tutorial: bool,
+    /// Stick deflection, 0..1, ignored as drift around center.
+    pub stick_deadzone: f32,
+    /// Response curve exponent past the deadzone: 1 is linear, higher is
+    /// gentler near center.
+    pub stick_sensitivity: f32,
+    pub control_scheme: ControlScheme,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            vsync: true,
+            resolution: 0,
+            thrust_curve: ThrustCurve::Linear,
+            theme: ThemeName::Modern,
+            high_contrast: false,
+            random_spawn: false,
+            show_tutorial: true,
+            stick_deadzone: 0.15,
+            stick_sensitivity: 1.0,
+            control_scheme: ControlScheme::Keyboard,
+        }
+    }
+}
+
+impl Settings {
+    /// Loads settings from the config dir, falling back to the defaults if
+    /// the file is missing or corrupt.
+    pub fn load() -> Self {
+        let Some(path) = config_path(SETTINGS_FILE) else {
+            return Self::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            info!("No settings at {}, using defaults", path.display());
+            return Self::default();
+        };
+        match toml::from_str::<Settings>(&contents) {
+            Ok(settings)
+                if settings.resolution < RESOLUTIONS.len()
+                    && (0.0..=MAX_DEADZONE).contains(&settings.stick_deadzone)
+                    && settings.stick_sensitivity > 0.0 =>
+            {
+                settings
+            }
+            Ok(_) => {
+                warn!(
+                    "Out-of-range settings in {}, using defaults",
+                    path.display()
+                );
+                Self::default()
+            }
+            Err(e) => {
+                warn!("Invalid settings in {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = config_path(SETTINGS_FILE) else {
+            return;
+        };
+        let contents = match toml::to_string_pretty(self) {
+            Ok(contents) => contents,
+            Err(e) => {
+                warn!("Failed to serialize settings: {}", e);
+                return;
+            }
+        };
+        if let Some(dir) = path.parent() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                warn!("Failed to create {}: {}", dir.display(), e);
+                return;
+            }
+        }
+        if let Err(e) = fs::write(&path, contents) {
+            warn!("Failed to save settings to {}: {}", path.display(), e);
+        }
+    }
+
+    /// The colors to draw with.
+    pub fn theme(&self) -> Theme {
+        if self.high_contrast {
+            ThemeName::HighContrast.theme()
+        } else {
+            self.theme.theme()
+        }
+    }
+
+    /// Maps a raw stick axis through the deadzone and sensitivity curve.
+    /// Inside the deadzone reads 0; beyond it the rest of the travel is
+    /// rescaled to 0..1 so output rises smoothly from the edge.
+    pub fn shape_stick(&self, value: f32) -> f32 {
+        let magnitude =
+            ((value.abs() - self.stick_deadzone) / (1.0 - self.stick_deadzone)).clamp(0.0, 1.0);
+        magnitude.powf(self.stick_sensitivity) * value.signum()
+    }
+
+    pub fn dimensions(&self) -> (f32, f32) {
+        RESOLUTIONS[self.resolution]
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        let (width, height) = self.dimensions();
+        WindowMode::default()
+            .dimensions(width, height)
+            .resizable(true)
+    }
+
+    /// Steps through `RESOLUTIONS`, wrapping at either end.
+    pub fn cycle_resolution(&mut self, forward: bool) {
+        let count = RESOLUTIONS.len();
+        self.resolution = if forward {
+            (self.resolution + 1) % count
+        } else {
+            (self.resolution + count - 1) % count
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stick(deadzone: f32, sensitivity: f32) -> Settings {
+        Settings {
+            stick_deadzone: deadzone,
+            stick_sensitivity: sensitivity,
+            ..Settings::default()
+        }
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-5,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn stick_inside_the_deadzone_reads_zero() {
+        let settings = stick(0.2, 1.0);
+        for value in [0.0, 0.05, -0.1, 0.2, -0.2] {
+            assert_eq!(settings.shape_stick(value), 0.0);
+        }
+    }
+
+    #[test]
+    fn stick_past_the_deadzone_rescales_smoothly() {
+        let settings = stick(0.2, 1.0);
+        assert_near(settings.shape_stick(0.21), 0.0125);
+        assert_near(settings.shape_stick(0.6), 0.5);
+        assert_near(settings.shape_stick(-0.6), -0.5);
+        assert_near(settings.shape_stick(1.0), 1.0);
+        assert_near(settings.shape_stick(-1.0), -1.0);
+    }
+
+    #[test]
+    fn stick_sensitivity_curves_the_response() {
+        let settings = stick(0.2, 2.0);
+        assert_near(settings.shape_stick(0.6), 0.25);
+        assert_near(settings.shape_stick(-0.6), -0.25);
+        assert_near(settings.shape_stick(1.0), 1.0);
+    }
+}
@@ -0,0 +1,73 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::warn;
+
+use crate::config::config_path;
+
+const TELEMETRY_FILE: &str = "landings.csv";
+const HEADER: &str = "timestamp,outcome,velocity,angle_error,fuel,level,score";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    PadLanding,
+    RoughLanding,
+    Crash,
+}
+
+impl Outcome {
+    fn name(self) -> &'static str {
+        match self {
+            Outcome::PadLanding => "pad",
+            Outcome::RoughLanding => "rough",
+            Outcome::Crash => "crash",
+        }
+    }
+}
+
+/// One touchdown, as recorded for balancing the game.
+pub struct LandingResult {
+    pub outcome: Outcome,
+    pub velocity: f32,    // m/s at touchdown
+    pub angle_error: f32, // radians off the surface
+    pub fuel: f32,
+    pub level: u32,
+    pub score: u32,
+}
+
+/// Appends `result` as a row of the landings CSV in the config dir, writing
+/// the header first if the file is new. Failures are only logged.
+pub fn log_outcome(result: &LandingResult) {
+    let Some(path) = config_path(TELEMETRY_FILE) else {
+        return;
+    };
+    if let Err(e) = append_row(&path, result) {
+        warn!("Failed to log landing to {}: {}", path.display(), e);
+    }
+}
+
+fn append_row(path: &Path, result: &LandingResult) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "{}", HEADER)?;
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    writeln!(
+        file,
+        "{},{},{:.3},{:.4},{:.1},{},{}",
+        timestamp,
+        result.outcome.name(),
+        result.velocity,
+        result.angle_error,
+        result.fuel,
+        result.level,
+        result.score
+    )
+}
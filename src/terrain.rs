@@ -1,56 +1,319 @@
-use ggez::graphics::{self, Canvas, Color, DrawMode, Mesh, MeshBuilder};
+use std::cell::RefCell;
+
+use ggez::graphics::{
+    self, Canvas, Color, DrawMode, Mesh, MeshBuilder, PxScale, Text, TextFragment,
+};
 use ggez::mint::Point2;
 use ggez::{Context, GameResult};
-use rand::Rng;
+use glam::Vec2;
+use log::warn;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
+use crate::difficulty::DifficultySettings;
 use crate::lander::LunarLander;
+use crate::level::LevelDef;
+use crate::screen::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::theme::Theme;
+
+pub const WORLD_WIDTH: f32 = 2400.0; // Playfield width; the screen shows SCREEN_WIDTH of it
+const POINT_SPACING: f32 = SCREEN_WIDTH / 99.0;
+const BONUS_PAD_WIDTH: usize = 6; // pads narrower than this earn a multiplier bonus
+const PAD_COUNT: usize = 3;
+const PAD_MARGIN: usize = 2; // points eased toward pad height on each side
+const MAX_PAD_ATTEMPTS: usize = 50;
+const REFUEL_PAD_CHANCE: f64 = 0.4; // per pad after the first
+const BASE_ROUGHNESS: f32 = 100.0; // height range of the surface on level 1
+const ROUGHNESS_PER_LEVEL: f32 = 25.0;
+const MAX_ROUGHNESS: f32 = 250.0;
+// Share of the displacement kept at each halving; higher is more jagged
+const BASE_PERSISTENCE: f32 = 0.5;
+const PERSISTENCE_PER_LEVEL: f32 = 0.03;
+const MAX_PERSISTENCE: f32 = 0.65;
+const OUTLINE_WIDTH: f32 = 1.5;
+const CEILING_SPACING: f32 = 40.0;
+const CEILING_DEPTH: (f32, f32) = (30.0, 110.0); // range of the cave roof's lower edge
+const STALACTITE_CHANCE: f64 = 0.15; // per roof point
+const STALACTITE_BOTTOM: f32 = 180.0; // keeps a gap above the highest possible ground
+const STALACTITE_WIDTH: f32 = 30.0;
+const SHAFT_HALF_WIDTH: f32 = 80.0; // open shaft above the spawn point
+const PAD_POST_HEIGHT: f32 = 8.0; // markers at pad ends when shape cues are on
+const HIGHLIGHT_MARGIN: f32 = 6.0; // glow extends this far past the pad
+const CORRIDOR_HEIGHT: f32 = 300.0; // how far the approach guide reaches above a pad
+const CORRIDOR_WIDTH: f32 = 1.0;
 
+/// A level's ground: the collision model plus the mesh that draws it. The
+/// mesh is only built the first time the terrain is drawn, and rebuilt if
+/// the theme changes.
 pub struct Terrain {
-    pub mesh: Mesh,
+    model: TerrainModel,
+    mesh: RefCell<Option<(Theme, Mesh)>>,
+}
+
+/// Terrain geometry and the queries on it, with no graphics attached, so
+/// collision and prediction work without a window.
+pub struct TerrainModel {
     points: Vec<TerrainPoint>,
+    pads: Vec<LandingPad>,
+    /// Closed rock outlines beyond the heightfield, such as a cave roof. They
+    /// can overhang, so collision uses segment tests rather than `height_at`.
+    outlines: Vec<Vec<Point2<f32>>>,
 }
 
-struct TerrainPoint {
-    position: Point2<f32>,
-    is_landing_pad: bool,
+/// A flat run of terrain points `start..end` that can be landed on.
+pub struct LandingPad {
+    pub start: usize,
+    pub end: usize,
+    pub multiplier: u32,
+    /// Refuel station: docking here tops up fuel instead of ending the level.
+    pub refuel: bool,
+}
+
+/// What the lander touched when a collision was detected.
+pub struct Contact {
+    /// Index into `Terrain::pads` if the contact was on a landing pad.
+    pub pad: Option<usize>,
+    pub part: ContactPart,
+}
+
+/// Which part of the lander hit the ground.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContactPart {
+    Legs,
+    /// Always a crash, whatever the speed.
+    Body,
+}
+
+pub struct TerrainPoint {
+    pub position: Point2<f32>,
+}
+
+/// Builds a level from `seed`. Higher `level`s (starting at 1) have rougher
+/// ground and narrower pads, within the difficulty's pad width range.
+pub fn generate_terrain(seed: u64, level: u32, settings: &DifficultySettings) -> Terrain {
+    let (points, pads) = generate_terrain_points(seed, level, settings);
+
+    Terrain::new(TerrainModel {
+        points,
+        pads,
+        outlines: Vec::new(),
+    })
 }
 
-pub fn generate_terrain(ctx: &mut Context) -> GameResult<Terrain> {
-    let mut rng = rand::thread_rng();
+/// The ground `generate_terrain` builds from `seed`, with no mesh or context
+/// involved. Pads come back with the points since placing them flattens the
+/// ground and draws from the same seeded stream.
+pub fn generate_terrain_points(
+    seed: u64,
+    level: u32,
+    settings: &DifficultySettings,
+) -> (Vec<TerrainPoint>, Vec<LandingPad>) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    generate_surface(&mut rng, level, settings)
+}
+
+/// Builds the terrain of a hand-made level, which `LevelDef::validate` has
+/// already checked.
+pub fn level_terrain(level: &LevelDef) -> Terrain {
+    let points = level
+        .terrain
+        .iter()
+        .map(|&[x, y]| TerrainPoint {
+            position: Point2 { x, y },
+        })
+        .collect();
+    let pads = level
+        .pads
+        .iter()
+        .map(|pad| LandingPad {
+            start: pad.start,
+            end: pad.end,
+            multiplier: pad.multiplier,
+            refuel: pad.refuel,
+        })
+        .collect();
+
+    Terrain::new(TerrainModel {
+        points,
+        pads,
+        outlines: Vec::new(),
+    })
+}
+
+/// Like `generate_terrain`, but roofs the level over with a jagged cave
+/// ceiling whose stalactites overhang the ground. A shaft is left open above
+/// the spawn point.
+pub fn generate_cave_terrain(seed: u64, level: u32, settings: &DifficultySettings) -> Terrain {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (points, pads) = generate_surface(&mut rng, level, settings);
+
+    // Trace the roof's lower edge left to right; the shaft splits it into
+    // two outlines, each closed along the top of the world
+    let shaft = WORLD_WIDTH / 2.0 - SHAFT_HALF_WIDTH..=WORLD_WIDTH / 2.0 + SHAFT_HALF_WIDTH;
+    let mut outlines = Vec::new();
+    let mut edge: Vec<Point2<f32>> = Vec::new();
+    let mut x = 0.0;
+    while x <= WORLD_WIDTH {
+        if shaft.contains(&x) {
+            if !edge.is_empty() {
+                outlines.push(close_roof(std::mem::take(&mut edge)));
+            }
+        } else if x + STALACTITE_WIDTH < WORLD_WIDTH
+            && !shaft.contains(&(x + STALACTITE_WIDTH))
+            && rng.gen_bool(STALACTITE_CHANCE)
+        {
+            // Sheer sides and a flat underside
+            let top = rng.gen_range(CEILING_DEPTH.0..CEILING_DEPTH.1);
+            let bottom = rng.gen_range(top..STALACTITE_BOTTOM);
+            edge.push(Point2 { x, y: top });
+            edge.push(Point2 { x, y: bottom });
+            edge.push(Point2 {
+                x: x + STALACTITE_WIDTH,
+                y: bottom,
+            });
+            edge.push(Point2 {
+                x: x + STALACTITE_WIDTH,
+                y: top,
+            });
+        } else {
+            let y = rng.gen_range(CEILING_DEPTH.0..CEILING_DEPTH.1);
+            edge.push(Point2 { x, y });
+        }
+        x += CEILING_SPACING;
+    }
+    if !edge.is_empty() {
+        outlines.push(close_roof(edge));
+    }
+
+    Terrain::new(TerrainModel {
+        points,
+        pads,
+        outlines,
+    })
+}
+
+/// Closes a stretch of roof edge up to the top of the world.
+fn close_roof(mut edge: Vec<Point2<f32>>) -> Vec<Point2<f32>> {
+    let first = edge[0].x;
+    let last = edge[edge.len() - 1].x;
+    edge.push(Point2 { x: last, y: 0.0 });
+    edge.push(Point2 { x: first, y: 0.0 });
+    edge
+}
+
+/// The heightfield ground and its landing pads.
+fn generate_surface(
+    rng: &mut StdRng,
+    level: u32,
+    settings: &DifficultySettings,
+) -> (Vec<TerrainPoint>, Vec<LandingPad>) {
+    let difficulty = level.saturating_sub(1);
+    let roughness = (BASE_ROUGHNESS + difficulty as f32 * ROUGHNESS_PER_LEVEL).min(MAX_ROUGHNESS);
+    let persistence =
+        (BASE_PERSISTENCE + difficulty as f32 * PERSISTENCE_PER_LEVEL).min(MAX_PERSISTENCE);
+    let min_pad_width = settings.min_pad_width;
+    let max_pad_width = settings
+        .max_pad_width
+        .saturating_sub(difficulty as usize)
+        .max(min_pad_width);
     let mut points = Vec::new();
 
     // Generate terrain points
-    let num_points = 100;
-    let dx = 800.0 / (num_points - 1) as f32;
+    let num_points = (WORLD_WIDTH / POINT_SPACING).round() as usize + 1;
+    let dx = WORLD_WIDTH / (num_points - 1) as f32;
 
-    for i in 0..num_points {
+    let heights = midpoint_displacement(rng, num_points, roughness, persistence);
+    for (i, height) in heights.into_iter().enumerate() {
         let x = i as f32 * dx;
-        let y = rng.gen_range(400.0..500.0);
+        let y = 500.0 - height;
         points.push(TerrainPoint {
             position: Point2 { x, y },
-            is_landing_pad: false,
         });
     }
 
-    // Add landing pads
-    for _ in 0..3 {
-        let pad_start = rng.gen_range(5..90);
-        let pad_width = 5;
+    // Add landing pads, retrying placements that would overlap an existing
+    // pad (including its margins)
+    let mut pads: Vec<LandingPad> = Vec::new();
+    let mut attempts = 0;
+    while pads.len() < PAD_COUNT && attempts < MAX_PAD_ATTEMPTS {
+        attempts += 1;
+        let pad_width = rng.gen_range(min_pad_width..=max_pad_width);
+        let pad_start = rng.gen_range(PAD_MARGIN..num_points - pad_width - PAD_MARGIN);
+        let pad_end = pad_start + pad_width;
+
+        let overlaps = pads.iter().any(|pad| {
+            pad_start < pad.end + 2 * PAD_MARGIN && pad.start < pad_end + 2 * PAD_MARGIN
+        });
+        if overlaps {
+            continue;
+        }
+
         let pad_height = points[pad_start].position.y;
+        for point in &mut points[pad_start..pad_end] {
+            point.position.y = pad_height;
+        }
 
-        for i in pad_start..pad_start + pad_width {
-            points[i].position.y = pad_height;
-            points[i].is_landing_pad = true;
+        // Ease the neighbours toward the pad so it doesn't sit on a cliff edge
+        for k in 1..=PAD_MARGIN {
+            let blend = k as f32 / (PAD_MARGIN + 1) as f32;
+            for i in [pad_start - k, pad_end - 1 + k] {
+                let y = points[i].position.y;
+                points[i].position.y = pad_height + (y - pad_height) * blend;
+            }
         }
+
+        // The first pad is always a normal one so the level can be finished
+        let refuel = !pads.is_empty() && rng.gen_bool(REFUEL_PAD_CHANCE);
+        pads.push(LandingPad {
+            start: pad_start,
+            end: pad_end,
+            multiplier: pad_multiplier(pad_width, pad_height),
+            refuel,
+        });
     }
+    if pads.len() < PAD_COUNT {
+        warn!("Only placed {} of {} landing pads", pads.len(), PAD_COUNT);
+    }
+
+    (points, pads)
+}
 
-    // Create mesh
-    let mesh = create_terrain_mesh(ctx, &points)?;
+/// Fractal heights in `0..=range` for `count` evenly spaced points: each
+/// midpoint is its neighbours' average plus a random offset, with the offset
+/// scaled by `persistence` at every halving so large ridges carry small detail.
+fn midpoint_displacement(rng: &mut StdRng, count: usize, range: f32, persistence: f32) -> Vec<f32> {
+    let mut heights = vec![0.0; count];
+    heights[0] = rng.gen_range(0.0..range);
+    heights[count - 1] = rng.gen_range(0.0..range);
+
+    let mut spans = vec![(0, count - 1, range / 2.0)];
+    while let Some((lo, hi, scale)) = spans.pop() {
+        if hi - lo < 2 {
+            continue;
+        }
+        let mid = (lo + hi) / 2;
+        let offset = rng.gen_range(-scale..=scale);
+        heights[mid] = ((heights[lo] + heights[hi]) / 2.0 + offset).clamp(0.0, range);
+        spans.push((lo, mid, scale * persistence));
+        spans.push((mid, hi, scale * persistence));
+    }
+    heights
+}
 
-    Ok(Terrain { mesh, points })
+/// Narrower and higher pads are harder to reach, so they pay out more.
+fn pad_multiplier(width: usize, height: f32) -> u32 {
+    let narrow_bonus = BONUS_PAD_WIDTH.saturating_sub(width) as u32;
+    let height_bonus = ((500.0 - height) / 50.0).clamp(0.0, 2.0) as u32;
+    1 + narrow_bonus + height_bonus
 }
 
-fn create_terrain_mesh(ctx: &mut Context, points: &[TerrainPoint]) -> GameResult<Mesh> {
+fn create_terrain_mesh(
+    ctx: &mut Context,
+    points: &[TerrainPoint],
+    pads: &[LandingPad],
+    outlines: &[Vec<Point2<f32>>],
+    theme: &Theme,
+) -> GameResult<Mesh> {
     let mut mb = MeshBuilder::new();
 
     // Draw terrain body
@@ -60,65 +323,691 @@ fn create_terrain_mesh(ctx: &mut Context, points: &[TerrainPoint]) -> GameResult
     }
 
     // Add bottom points to close the shape
-    mesh_points.push(Point2 { x: 800.0, y: 600.0 });
-    mesh_points.push(Point2 { x: 0.0, y: 600.0 });
+    mesh_points.push(Point2 {
+        x: WORLD_WIDTH,
+        y: SCREEN_HEIGHT,
+    });
+    mesh_points.push(Point2 {
+        x: 0.0,
+        y: SCREEN_HEIGHT,
+    });
+
+    mb.polygon(DrawMode::fill(), &mesh_points, theme.terrain_fill)?;
+
+    // Trace the horizon along the surface points only, so the closing
+    // corners of the fill aren't joined up
+    let surface: Vec<Point2<f32>> = points.iter().map(|point| point.position).collect();
+    mb.line(&surface, OUTLINE_WIDTH, theme.terrain_outline)?;
 
-    mb.polygon(
-        DrawMode::fill(),
-        &mesh_points,
-        Color::from_rgb(150, 150, 150),
-    )?;
+    // Draw cave rock
+    for outline in outlines {
+        mb.polygon(DrawMode::fill(), outline, theme.rock)?;
+    }
 
     // Draw landing pads with different color
-    for i in 0..points.len() - 1 {
-        if points[i].is_landing_pad {
+    for pad in pads {
+        for i in pad.start..pad.end - 1 {
             mb.line(
                 &[points[i].position, points[i + 1].position],
                 2.0,
-                Color::from_rgb(0, 255, 0),
+                pad_color(pad, theme),
             )?;
         }
+        // Posts at either end mark a pad without relying on its color
+        if theme.shape_cues {
+            for end in [points[pad.start].position, points[pad.end - 1].position] {
+                let top = Point2 {
+                    x: end.x,
+                    y: end.y - PAD_POST_HEIGHT,
+                };
+                mb.line(&[end, top], 2.0, pad_color(pad, theme))?;
+            }
+        }
     }
 
     Ok(Mesh::from_data(ctx, mb.build()))
 }
 
+fn pad_color(pad: &LandingPad, theme: &Theme) -> Color {
+    if pad.refuel {
+        theme.refuel_pad
+    } else {
+        theme.pad
+    }
+}
+
 impl Terrain {
-    pub fn draw(&self, canvas: &mut Canvas) -> GameResult {
-        canvas.draw(&self.mesh, graphics::DrawParam::default());
+    fn new(model: TerrainModel) -> Self {
+        Terrain {
+            model,
+            mesh: RefCell::new(None),
+        }
+    }
+
+    pub fn model(&self) -> &TerrainModel {
+        &self.model
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, theme: &Theme) -> GameResult {
+        canvas.draw(&self.mesh(ctx, theme)?, graphics::DrawParam::default());
+
+        // Label each pad with its score multiplier, or as a refuel station
+        let points = &self.model.points;
+        for pad in &self.model.pads {
+            let left = points[pad.start].position;
+            let right = points[pad.end - 1].position;
+            let label = if pad.refuel {
+                "FUEL".to_string()
+            } else if theme.shape_cues {
+                format!("SAFE x{}", pad.multiplier)
+            } else {
+                format!("x{}", pad.multiplier)
+            };
+            let label = Text::new(TextFragment::new(label).scale(PxScale::from(14.0)));
+            canvas.draw(
+                &label,
+                graphics::DrawParam::default()
+                    .dest([(left.x + right.x) / 2.0, left.y - 12.0])
+                    .offset([0.5, 0.5])
+                    .color(pad_color(pad, theme)),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// A glowing outline around `pad`; `pulse` (0..1) sets its brightness.
+    pub fn draw_pad_highlight(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        pad: usize,
+        pulse: f32,
+        theme: &Theme,
+    ) -> GameResult {
+        let points = &self.model.points;
+        let left = points[self.model.pads[pad].start].position;
+        let right = points[self.model.pads[pad].end - 1].position;
+        let bounds = graphics::Rect::new(
+            left.x - HIGHLIGHT_MARGIN,
+            left.y - HIGHLIGHT_MARGIN * 2.0,
+            right.x - left.x + HIGHLIGHT_MARGIN * 2.0,
+            HIGHLIGHT_MARGIN * 3.0,
+        );
+        let mut color = pad_color(&self.model.pads[pad], theme);
+        color.a = 0.3 + 0.7 * pulse;
+        let glow = Mesh::new_rectangle(ctx, DrawMode::stroke(2.0), bounds, color)?;
+        canvas.draw(&glow, graphics::DrawParam::default());
+        Ok(())
+    }
+
+    /// Faint funnel above `pad` marking the approach corridor for a lander
+    /// that may lean up to `lean` radians off vertical.
+    pub fn draw_approach_corridor(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        pad: usize,
+        lean: f32,
+        color: Color,
+    ) -> GameResult {
+        let [top_left, bottom_left, bottom_right, top_right] =
+            self.model.approach_corridor(pad, lean);
+        let mut mb = MeshBuilder::new();
+        mb.line(&[top_left, bottom_left], CORRIDOR_WIDTH, color)?;
+        mb.line(&[bottom_right, top_right], CORRIDOR_WIDTH, color)?;
+        canvas.draw(
+            &Mesh::from_data(ctx, mb.build()),
+            graphics::DrawParam::default(),
+        );
         Ok(())
     }
 
-    pub fn check_collision(&self, lander: &mut LunarLander) -> bool {
+    pub fn in_approach_corridor(&self, pad: usize, lean: f32, point: Point2<f32>) -> bool {
+        self.model.in_approach_corridor(pad, lean, point)
+    }
+
+    fn mesh(&self, ctx: &mut Context, theme: &Theme) -> GameResult<Mesh> {
+        let mut cache = self.mesh.borrow_mut();
+        if let Some((built_with, mesh)) = &*cache {
+            if built_with == theme {
+                return Ok(mesh.clone());
+            }
+        }
+        let model = &self.model;
+        let mesh = create_terrain_mesh(ctx, &model.points, &model.pads, &model.outlines, theme)?;
+        *cache = Some((*theme, mesh.clone()));
+        Ok(mesh)
+    }
+
+    pub fn height_at(&self, x: f32) -> f32 {
+        self.model.height_at(x)
+    }
+
+    pub fn pad_surface_y(&self, pad: usize) -> f32 {
+        self.model.pad_surface_y(pad)
+    }
+
+    pub fn pad_center_x(&self, pad: usize) -> f32 {
+        self.model.pad_center_x(pad)
+    }
+
+    pub fn surface_points(&self) -> Vec<Point2<f32>> {
+        self.model.surface_points()
+    }
+
+    pub fn pads(&self) -> &[LandingPad] {
+        self.model.pads()
+    }
+
+    pub fn is_touching(&self, lander: &LunarLander) -> bool {
+        self.model.is_touching(lander)
+    }
+
+    pub fn check_collision(&self, lander: &mut LunarLander) -> Option<Contact> {
+        self.model.check_collision(lander)
+    }
+}
+
+impl TerrainModel {
+    /// Surface y (screen space) under `x`, clamped to the terrain's ends.
+    pub fn height_at(&self, x: f32) -> f32 {
+        let first = self.points[0].position;
+        let last = self.points[self.points.len() - 1].position;
+        let x = x.clamp(first.x, last.x);
+
+        // Points are sorted by x; find the first one at or past x
+        let i = self
+            .points
+            .partition_point(|p| p.position.x < x)
+            .clamp(1, self.points.len() - 1);
+        interpolate_y(x, self.points[i - 1].position, self.points[i].position)
+    }
+
+    /// Screen y of a pad's flat surface.
+    pub fn pad_surface_y(&self, pad: usize) -> f32 {
+        self.points[self.pads[pad].start].position.y
+    }
+
+    pub fn pad_center_x(&self, pad: usize) -> f32 {
+        let pad = &self.pads[pad];
+        (self.points[pad.start].position.x + self.points[pad.end - 1].position.x) / 2.0
+    }
+
+    /// The scoring (non-refuel) pad whose center is closest to `x`.
+    pub fn nearest_pad(&self, x: f32) -> Option<usize> {
+        (0..self.pads.len())
+            .filter(|&pad| !self.pads[pad].refuel)
+            .min_by(|&a, &b| {
+                let da = (self.pad_center_x(a) - x).abs();
+                let db = (self.pad_center_x(b) - x).abs();
+                da.total_cmp(&db)
+            })
+    }
+
+    /// The corridor above `pad` as top-left, bottom-left, bottom-right,
+    /// top-right. It spans the pad at the surface and widens with height as
+    /// far as a lander leaning `lean` off vertical would drift on the way down.
+    pub fn approach_corridor(&self, pad: usize, lean: f32) -> [Point2<f32>; 4] {
+        let left = self.points[self.pads[pad].start].position;
+        let right = self.points[self.pads[pad].end - 1].position;
+        let spread = CORRIDOR_HEIGHT * lean.tan();
+        let top = left.y - CORRIDOR_HEIGHT;
+        [
+            Point2 {
+                x: left.x - spread,
+                y: top,
+            },
+            left,
+            right,
+            Point2 {
+                x: right.x + spread,
+                y: top,
+            },
+        ]
+    }
+
+    /// Whether `point` is inside `pad`'s approach corridor.
+    pub fn in_approach_corridor(&self, pad: usize, lean: f32, point: Point2<f32>) -> bool {
+        let height = self.pad_surface_y(pad) - point.y;
+        if !(0.0..=CORRIDOR_HEIGHT).contains(&height) {
+            return false;
+        }
+        let pad_half_width = {
+            let pad = &self.pads[pad];
+            (self.points[pad.end - 1].position.x - self.points[pad.start].position.x) / 2.0
+        };
+        (point.x - self.pad_center_x(pad)).abs() <= pad_half_width + height * lean.tan()
+    }
+
+    /// The ground profile, left to right.
+    pub fn surface_points(&self) -> Vec<Point2<f32>> {
+        self.points.iter().map(|point| point.position).collect()
+    }
+
+    pub fn pads(&self) -> &[LandingPad] {
+        &self.pads
+    }
+
+    /// Returns the pad whose flat surface includes the segment starting at `segment`.
+    fn pad_for_segment(&self, segment: usize) -> Option<usize> {
+        self.pads
+            .iter()
+            .position(|pad| segment >= pad.start && segment + 1 < pad.end)
+    }
+
+    /// Whether either leg is currently at or below the surface, without
+    /// touching the lander's landing latch.
+    pub fn is_touching(&self, lander: &LunarLander) -> bool {
+        lander.get_legs_points().into_iter().any(|leg| {
+            self.points
+                .windows(2)
+                .any(|pair| point_in_segment(leg, pair[0].position, pair[1].position))
+        })
+    }
+
+    pub fn check_collision(&self, lander: &mut LunarLander) -> Option<Contact> {
         let legs = lander.get_legs_points();
+        let previous_legs = lander.previous_legs_points().to_vec();
 
-        for leg in legs {
+        for (leg, previous) in legs.into_iter().zip(previous_legs) {
             for i in 0..self.points.len() - 1 {
                 let p1 = self.points[i].position;
                 let p2 = self.points[i + 1].position;
 
-                if point_in_segment(leg, p1, p2) {
-                    // Calculate surface angle for landing check
-                    let dx = p2.x - p1.x;
-                    let dy = p2.y - p1.y;
-                    let surface_angle = (dy / dx).atan();
+                // Sweep from last tick's leg position so fast landers can't tunnel
+                if point_in_segment(leg, p1, p2) || segments_intersect(previous, leg, p1, p2) {
+                    let pad = self.pad_for_segment(i);
+                    lander.check_landing_safety(surface_angle(p1, p2), pad.is_some());
+                    return Some(Contact {
+                        pad,
+                        part: ContactPart::Legs,
+                    });
+                }
+            }
+        }
+
+        // Cave rock is never a landing surface
+        let previous_legs = lander.previous_legs_points().to_vec();
+        let legs = lander.get_legs_points();
+        let vertices = lander.get_vertices();
+        for outline in &self.outlines {
+            let inside = vertices
+                .iter()
+                .chain(&legs)
+                .any(|&point| point_in_polygon(point, outline));
+            let swept = outline
+                .iter()
+                .zip(outline.iter().cycle().skip(1))
+                .any(|(&e1, &e2)| {
+                    legs.iter()
+                        .zip(&previous_legs)
+                        .any(|(&leg, &previous)| segments_intersect(previous, leg, e1, e2))
+                });
+            if inside || swept {
+                lander.crash();
+                return Some(Contact {
+                    pad: None,
+                    part: ContactPart::Body,
+                });
+            }
+        }
 
-                    lander.check_landing_safety(surface_angle);
-                    return true;
+        // Legs clear: a body vertex in the ground means the lander hit nose
+        // or side first
+        for vertex in lander.get_vertices() {
+            for i in 0..self.points.len() - 1 {
+                let p1 = self.points[i].position;
+                let p2 = self.points[i + 1].position;
+                if point_in_segment(vertex, p1, p2)
+                    && lander.check_body_impact(surface_angle(p1, p2))
+                {
+                    return Some(Contact {
+                        pad: self.pad_for_segment(i),
+                        part: ContactPart::Body,
+                    });
                 }
             }
         }
-        false
+        None
     }
 }
 
+/// Slope of the segment `p1`-`p2`, for the landing attitude check.
+fn surface_angle(p1: Point2<f32>, p2: Point2<f32>) -> f32 {
+    let dx = p2.x - p1.x;
+    let dy = p2.y - p1.y;
+    (dy / dx).atan()
+}
+
 fn point_in_segment(point: Point2<f32>, p1: Point2<f32>, p2: Point2<f32>) -> bool {
     if point.x < p1.x.min(p2.x) || point.x > p1.x.max(p2.x) {
         return false;
     }
 
-    let t = (point.x - p1.x) / (p2.x - p1.x);
-    let interpolated_y = p1.y + t * (p2.y - p1.y);
+    point.y >= interpolate_y(point.x, p1, p2)
+}
+
+/// Even-odd test against a closed outline.
+fn point_in_polygon(point: Point2<f32>, outline: &[Point2<f32>]) -> bool {
+    let mut inside = false;
+    for (a, b) in outline.iter().zip(outline.iter().cycle().skip(1)) {
+        if (a.y > point.y) != (b.y > point.y) {
+            let x = a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+    }
+    inside
+}
+
+fn interpolate_y(x: f32, p1: Point2<f32>, p2: Point2<f32>) -> f32 {
+    let t = (x - p1.x) / (p2.x - p1.x);
+    p1.y + t * (p2.y - p1.y)
+}
+
+fn segments_intersect(a1: Point2<f32>, a2: Point2<f32>, b1: Point2<f32>, b2: Point2<f32>) -> bool {
+    let (a1, a2, b1, b2) = (
+        Vec2::from(a1),
+        Vec2::from(a2),
+        Vec2::from(b1),
+        Vec2::from(b2),
+    );
+    let da = a2 - a1;
+    let db = b2 - b1;
+    let denom = da.perp_dot(db);
+    if denom.abs() < f32::EPSILON {
+        // Parallel (or degenerate) segments
+        return false;
+    }
+
+    let t = (b1 - a1).perp_dot(db) / denom;
+    let u = (b1 - a1).perp_dot(da) / denom;
+    (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::difficulty::Difficulty;
+    use crate::physics::PhysicsConfig;
+
+    fn model(points: &[[f32; 2]]) -> TerrainModel {
+        TerrainModel {
+            points: points
+                .iter()
+                .map(|&[x, y]| TerrainPoint {
+                    position: Point2 { x, y },
+                })
+                .collect(),
+            pads: Vec::new(),
+            outlines: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn lander_falling_into_a_gap_crashes_on_the_floor() {
+        // A pit whose bottom is below the screen
+        let terrain = model(&[
+            [0.0, 500.0],
+            [1000.0, 500.0],
+            [1001.0, 700.0],
+            [1399.0, 700.0],
+            [1400.0, 500.0],
+            [WORLD_WIDTH, 500.0],
+        ]);
+        let mut lander = LunarLander::with_physics(1200.0, 100.0, PhysicsConfig::lunar());
+        for _ in 0..10_000 {
+            lander.update();
+            assert!(terrain.check_collision(&mut lander).is_none());
+            if lander.check_floor() {
+                assert!(lander.is_landing_checked());
+                assert!(!lander.is_landed_safely());
+                return;
+            }
+        }
+        panic!("still falling at y = {}", lander.position.y);
+    }
+
+    #[test]
+    fn terrain_points_match_the_generated_terrain() {
+        let settings = Difficulty::Normal.settings();
+        let (points, pads) = generate_terrain_points(42, 3, &settings);
+        let terrain = generate_terrain(42, 3, &settings);
+        assert_eq!(positions(&points), terrain.surface_points());
+        assert_eq!(pads.len(), terrain.pads().len());
+    }
+
+    fn positions(points: &[TerrainPoint]) -> Vec<Point2<f32>> {
+        points.iter().map(|point| point.position).collect()
+    }
 
-    point.y >= interpolated_y
+    #[test]
+    fn same_seed_generates_identical_terrain() {
+        let settings = Difficulty::Normal.settings();
+        let (first, _) = generate_terrain_points(1234, 1, &settings);
+        let (second, _) = generate_terrain_points(1234, 1, &settings);
+        let (other, _) = generate_terrain_points(1235, 1, &settings);
+        assert_eq!(positions(&first), positions(&second));
+        assert_ne!(positions(&first), positions(&other));
+    }
+
+    #[test]
+    fn pads_never_overlap_and_stay_in_bounds() {
+        for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+            let settings = difficulty.settings();
+            for seed in 0..200 {
+                let (points, pads) =
+                    generate_terrain_points(seed, 1 + (seed % 8) as u32, &settings);
+                assert!(!pads.is_empty());
+                for (i, pad) in pads.iter().enumerate() {
+                    assert!(pad.start >= PAD_MARGIN && pad.start < pad.end);
+                    assert!(pad.end + PAD_MARGIN <= points.len());
+                    let y = points[pad.start].position.y;
+                    assert!(points[pad.start..pad.end].iter().all(|p| p.position.y == y));
+                    for other in &pads[i + 1..] {
+                        assert!(pad.end <= other.start || other.end <= pad.start);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn teleporting_across_a_spike_still_collides() {
+        let flat = model(&[[0.0, 500.0], [WORLD_WIDTH, 500.0]]);
+        let spiked = model(&[
+            [0.0, 500.0],
+            [300.0, 500.0],
+            [310.0, 250.0],
+            [320.0, 500.0],
+            [WORLD_WIDTH, 500.0],
+        ]);
+        for (terrain, hits) in [(flat, false), (spiked, true)] {
+            let mut lander = LunarLander::with_physics(200.0, 300.0, PhysicsConfig::lunar());
+            // One step's worth of travel, well clear of the ground at both ends
+            lander.position.x = 500.0;
+            let contact = terrain.check_collision(&mut lander);
+            assert_eq!(contact.is_some(), hits);
+            if let Some(contact) = contact {
+                assert_eq!(contact.part, ContactPart::Legs);
+                assert!(lander.is_landing_checked());
+            }
+        }
+    }
+
+    #[test]
+    fn height_at_pad_centers_is_the_pad_surface() {
+        let terrain = generate_terrain(7, 2, &Difficulty::Normal.settings());
+        for pad in 0..terrain.pads().len() {
+            let center = terrain.pad_center_x(pad);
+            assert_eq!(terrain.height_at(center), terrain.pad_surface_y(pad));
+        }
+    }
+
+    #[test]
+    fn height_at_interpolates_slopes_and_clamps_the_ends() {
+        let terrain = model(&[[0.0, 500.0], [100.0, 400.0], [200.0, 400.0], [300.0, 550.0]]);
+        assert_eq!(terrain.height_at(50.0), 450.0);
+        assert_eq!(terrain.height_at(100.0), 400.0);
+        assert_eq!(terrain.height_at(150.0), 400.0);
+        assert_eq!(terrain.height_at(250.0), 475.0);
+        assert_eq!(terrain.height_at(-40.0), 500.0);
+        assert_eq!(terrain.height_at(900.0), 550.0);
+    }
+
+    /// Steps `lander` until it touches `terrain`, returning the contact.
+    fn fall_onto(terrain: &TerrainModel, lander: &mut LunarLander) -> Contact {
+        for _ in 0..600 {
+            lander.update();
+            if let Some(contact) = terrain.check_collision(lander) {
+                return contact;
+            }
+        }
+        panic!("never touched down, at y = {}", lander.position.y);
+    }
+
+    #[test]
+    fn lander_can_land_lift_off_and_land_again() {
+        let mut terrain = model(&[[0.0, 500.0], [400.0, 500.0], [800.0, 500.0]]);
+        terrain.pads.push(LandingPad {
+            start: 0,
+            end: 3,
+            multiplier: 1,
+            refuel: true,
+        });
+        // Legs just above the pad, sinking gently
+        let mut lander = LunarLander::with_physics(400.0, 502.5, PhysicsConfig::lunar());
+        lander.velocity = Vec2::new(0.0, -1.0);
+
+        let contact = fall_onto(&terrain, &mut lander);
+        assert_eq!(contact.pad, Some(0));
+        assert!(lander.is_pad_landing());
+        lander.settle(terrain.pad_surface_y(0));
+        assert!(!terrain.is_touching(&lander));
+
+        lander.liftoff();
+        assert!(!lander.is_landing_checked());
+        assert!(!lander.is_landed_safely());
+
+        // A small hop, then back down onto the same pad
+        lander.velocity = Vec2::new(0.0, 1.0);
+        lander.update();
+        assert!(terrain.check_collision(&mut lander).is_none());
+        fall_onto(&terrain, &mut lander);
+        assert!(lander.is_landing_checked());
+        assert!(lander.is_pad_landing());
+    }
+
+    #[test]
+    fn gentle_contact_off_a_pad_is_only_a_rough_landing() {
+        for on_pad in [true, false] {
+            let mut terrain = model(&[[0.0, 500.0], [400.0, 500.0], [800.0, 500.0]]);
+            if on_pad {
+                terrain.pads.push(LandingPad {
+                    start: 0,
+                    end: 3,
+                    multiplier: 1,
+                    refuel: false,
+                });
+            }
+            let mut lander = LunarLander::with_physics(400.0, 502.5, PhysicsConfig::lunar());
+            lander.velocity = Vec2::new(0.0, -1.0);
+
+            let contact = fall_onto(&terrain, &mut lander);
+            assert_eq!(contact.pad.is_some(), on_pad);
+            assert!(lander.is_landed_safely());
+            assert_eq!(lander.is_pad_landing(), on_pad);
+        }
+    }
+
+    #[test]
+    fn nose_into_a_peak_with_the_legs_clear_crashes() {
+        let mut lander = LunarLander::with_physics(400.0, 300.0, PhysicsConfig::lunar());
+        lander.angle = 0.5;
+        // A needle whose tip is just above the nose
+        let nose = lander.get_vertices()[0];
+        let terrain = model(&[
+            [0.0, 550.0],
+            [nose.x - 2.0, 550.0],
+            [nose.x, nose.y - 2.0],
+            [nose.x + 2.0, 550.0],
+            [WORLD_WIDTH, 550.0],
+        ]);
+        assert!(lander
+            .get_legs_points()
+            .iter()
+            .all(|leg| (leg.x - nose.x).abs() > 2.0 && leg.y < 550.0));
+
+        let contact = terrain.check_collision(&mut lander).expect("nose contact");
+        assert_eq!(contact.part, ContactPart::Body);
+        assert!(lander.is_landing_checked());
+        assert!(!lander.is_landed_safely());
+    }
+
+    /// Flat ground at y 500 with one rock outline.
+    fn cave(outline: &[[f32; 2]]) -> TerrainModel {
+        let mut terrain = model(&[[0.0, 500.0], [WORLD_WIDTH, 500.0]]);
+        terrain
+            .outlines
+            .push(outline.iter().map(|&[x, y]| Point2 { x, y }).collect());
+        terrain
+    }
+
+    /// Whether a lander spawned at `from` and moved to `to` in one step
+    /// crashes into `terrain`.
+    fn crashes_moving(terrain: &TerrainModel, from: [f32; 2], to: [f32; 2]) -> bool {
+        let mut lander = LunarLander::with_physics(from[0], from[1], PhysicsConfig::lunar());
+        lander.position = Point2 { x: to[0], y: to[1] };
+        match terrain.check_collision(&mut lander) {
+            Some(contact) => {
+                assert_eq!(contact.part, ContactPart::Body);
+                assert!(!lander.is_landed_safely());
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[test]
+    fn lander_collides_with_a_vertical_wall() {
+        let wall = cave(&[[500.0, 0.0], [520.0, 0.0], [520.0, 500.0], [500.0, 500.0]]);
+        assert!(!crashes_moving(&wall, [470.0, 300.0], [470.0, 300.0]));
+        // A leg pushed into the rock
+        assert!(crashes_moving(&wall, [470.0, 300.0], [490.0, 300.0]));
+        // Straight through it in one step
+        assert!(crashes_moving(&wall, [470.0, 300.0], [560.0, 300.0]));
+    }
+
+    #[test]
+    fn lander_collides_with_the_underside_of_an_overhang() {
+        // A shelf hanging over open ground, its underside at y 250
+        let shelf = cave(&[[300.0, 0.0], [600.0, 0.0], [600.0, 250.0], [300.0, 250.0]]);
+        // Under the shelf with the heightfield far below: clear
+        assert!(!crashes_moving(&shelf, [450.0, 300.0], [450.0, 260.0]));
+        // Rising into it
+        assert!(crashes_moving(&shelf, [450.0, 300.0], [450.0, 255.0]));
+    }
+
+    #[test]
+    fn generated_terrain_stays_on_screen() {
+        let settings = Difficulty::Hard.settings();
+        for level in [1, 5, 20] {
+            for seed in 0..100 {
+                let (points, _) = generate_terrain_points(seed, level, &settings);
+                assert_eq!(points[0].position.x, 0.0);
+                assert!((points[points.len() - 1].position.x - WORLD_WIDTH).abs() < 1e-2);
+                for point in &points {
+                    assert!(
+                        (0.0..SCREEN_HEIGHT).contains(&point.position.y),
+                        "seed {} level {}: y = {}",
+                        seed,
+                        level,
+                        point.position.y
+                    );
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,107 @@
+use ggez::graphics::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::particles::ExplosionPalette;
+
+/// Colors for everything drawn in the world and on the HUD.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Theme {
+    pub background: Color,
+    pub lander_body: Color,
+    pub flame: Color,
+    pub terrain_fill: Color,
+    pub terrain_outline: Color,
+    pub rock: Color,
+    pub pad: Color,
+    pub refuel_pad: Color,
+    pub stars: Color,
+    pub hud_text: Color,
+    pub explosion: ExplosionPalette,
+    /// Back up color coding with labels, markers and symbols.
+    pub shape_cues: bool,
+}
+
+/// The selectable `Theme` presets, persisted by name in the settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThemeName {
+    /// Green phosphor, like the vector arcade original.
+    Classic,
+    #[default]
+    Modern,
+    /// Bright shapes on black, for legibility.
+    HighContrast,
+}
+
+impl ThemeName {
+    const ALL: [ThemeName; 3] = [
+        ThemeName::Classic,
+        ThemeName::Modern,
+        ThemeName::HighContrast,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemeName::Classic => "Classic",
+            ThemeName::Modern => "Modern",
+            ThemeName::HighContrast => "High contrast",
+        }
+    }
+
+    /// The next preset in the list, wrapping at either end.
+    pub fn cycle(self, forward: bool) -> Self {
+        let count = Self::ALL.len();
+        let index = Self::ALL.iter().position(|&t| t == self).unwrap_or(0);
+        Self::ALL[if forward {
+            (index + 1) % count
+        } else {
+            (index + count - 1) % count
+        }]
+    }
+
+    pub fn theme(self) -> Theme {
+        match self {
+            ThemeName::Classic => Theme {
+                background: Color::BLACK,
+                lander_body: Color::new(0.3, 1.0, 0.3, 1.0),
+                flame: Color::new(0.7, 1.0, 0.5, 1.0),
+                terrain_fill: Color::new(0.0, 0.15, 0.0, 1.0),
+                terrain_outline: Color::new(0.3, 1.0, 0.3, 1.0),
+                rock: Color::new(0.0, 0.3, 0.0, 1.0),
+                pad: Color::new(0.8, 1.0, 0.8, 1.0),
+                refuel_pad: Color::new(0.5, 1.0, 0.9, 1.0),
+                stars: Color::new(0.5, 1.0, 0.5, 1.0),
+                hud_text: Color::new(0.3, 1.0, 0.3, 1.0),
+                explosion: ExplosionPalette::TOXIC,
+                shape_cues: false,
+            },
+            ThemeName::Modern => Theme {
+                background: Color::new(0.0, 0.0, 0.08, 1.0),
+                lander_body: Color::WHITE,
+                flame: Color::new(1.0, 0.5, 0.0, 1.0),
+                terrain_fill: Color::from_rgb(150, 150, 150),
+                terrain_outline: Color::from_rgb(210, 210, 210),
+                rock: Color::from_rgb(120, 120, 120),
+                pad: Color::from_rgb(0, 255, 0),
+                refuel_pad: Color::CYAN,
+                stars: Color::WHITE,
+                hud_text: Color::WHITE,
+                explosion: ExplosionPalette::CLASSIC,
+                shape_cues: false,
+            },
+            ThemeName::HighContrast => Theme {
+                background: Color::BLACK,
+                lander_body: Color::YELLOW,
+                flame: Color::new(1.0, 0.3, 0.0, 1.0),
+                terrain_fill: Color::WHITE,
+                terrain_outline: Color::WHITE,
+                rock: Color::from_rgb(180, 180, 180),
+                pad: Color::MAGENTA,
+                refuel_pad: Color::CYAN,
+                stars: Color::WHITE,
+                hud_text: Color::WHITE,
+                explosion: ExplosionPalette::CLASSIC,
+                shape_cues: true,
+            },
+        }
+    }
+}
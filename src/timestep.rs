@@ -0,0 +1,94 @@
+/// Accumulates real frame time and hands out whole fixed-size physics ticks,
+/// so the simulation runs at the same rate whatever the render rate.
+pub struct FixedTimestep {
+    dt: f32,
+    accumulator: f32,
+}
+
+// Cap on time banked per frame, so a long stall doesn't trigger a burst of
+// catch-up ticks (the "spiral of death")
+const MAX_FRAME_TIME: f32 = 0.25;
+
+impl FixedTimestep {
+    pub fn new(dt: f32) -> Self {
+        FixedTimestep {
+            dt,
+            accumulator: 0.0,
+        }
+    }
+
+    /// Adds `delta` seconds and returns how many ticks are now due.
+    pub fn advance(&mut self, delta: f32) -> u32 {
+        self.accumulator += delta.clamp(0.0, MAX_FRAME_TIME);
+
+        let mut ticks = 0;
+        while self.accumulator >= self.dt {
+            self.accumulator -= self.dt;
+            ticks += 1;
+        }
+        ticks
+    }
+
+    /// How far between the last tick and the next the banked time reaches,
+    /// 0..1, for interpolating what's drawn.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.dt).clamp(0.0, 1.0)
+    }
+
+    /// Drops any banked time, e.g. when resuming from pause.
+    pub fn reset(&mut self) {
+        self.accumulator = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A power-of-two tick, so the sums below are exact
+    const DT: f32 = 1.0 / 64.0;
+
+    #[test]
+    fn short_frames_bank_time_until_a_tick_is_due() {
+        let mut timestep = FixedTimestep::new(DT);
+        assert_eq!(timestep.advance(DT / 2.0), 0);
+        assert_eq!(timestep.alpha(), 0.5);
+        assert_eq!(timestep.advance(DT / 2.0), 1);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+
+    #[test]
+    fn long_frames_run_several_ticks_and_keep_the_rest() {
+        let mut timestep = FixedTimestep::new(DT);
+        assert_eq!(timestep.advance(3.5 * DT), 3);
+        assert_eq!(timestep.alpha(), 0.5);
+        assert_eq!(timestep.advance(0.5 * DT), 1);
+    }
+
+    #[test]
+    fn frame_rate_does_not_change_the_tick_count() {
+        for fps in [30.0, 60.0, 144.0, 240.0] {
+            let mut timestep = FixedTimestep::new(1.0 / 60.0);
+            let ticks: u32 = (0..(fps as u32 * 10))
+                .map(|_| timestep.advance(1.0 / fps))
+                .sum();
+            assert!(
+                (599..=600).contains(&ticks),
+                "{} ticks at {} fps",
+                ticks,
+                fps
+            );
+        }
+    }
+
+    #[test]
+    fn stalls_and_bad_deltas_are_capped() {
+        let mut timestep = FixedTimestep::new(DT);
+        assert_eq!(timestep.advance(5.0), (MAX_FRAME_TIME / DT) as u32);
+        assert_eq!(timestep.advance(-1.0), 0);
+        timestep.advance(DT / 2.0);
+        timestep.reset();
+        assert_eq!(timestep.alpha(), 0.0);
+        assert_eq!(timestep.advance(DT / 2.0), 0);
+    }
+}
@@ -0,0 +1,127 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const WINDY_FROM_LEVEL: u32 = 3;
+const BASE_STRENGTH_PER_LEVEL: f32 = 0.1; // m/s² of steady wind per level past the first windy one
+const MAX_BASE_STRENGTH: f32 = 0.6;
+const OSCILLATION_PERIOD: f32 = 8.0; // seconds
+const GUST_CHANCE: f64 = 0.005; // per tick while no gust is blowing
+const GUST_STRENGTH: f32 = 1.0; // m/s²
+const GUST_DURATION: (f32, f32) = (0.5, 2.0); // seconds
+
+/// A horizontal push on the lander: a steady base that swings back and forth
+/// over time, plus occasional gusts. Positive is to the right.
+pub struct Wind {
+    base: f32,
+    time: f32,
+    gust: f32,
+    gust_left: f32,
+    gusty: bool,
+    rng: StdRng,
+}
+
+impl Wind {
+    pub fn calm() -> Self {
+        Wind {
+            base: 0.0,
+            time: 0.0,
+            gust: 0.0,
+            gust_left: 0.0,
+            gusty: false,
+            rng: StdRng::seed_from_u64(0),
+        }
+    }
+
+    /// A steady `strength` swinging as usual, without gusts.
+    pub fn steady(strength: f32) -> Self {
+        Wind {
+            base: strength,
+            ..Self::calm()
+        }
+    }
+
+    /// Early levels are calm; later ones blow harder. Seeded from the level
+    /// so replays see the same gusts.
+    pub fn for_level(seed: u64, level: u32) -> Self {
+        if level < WINDY_FROM_LEVEL {
+            return Self::calm();
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let strength = ((level - WINDY_FROM_LEVEL + 1) as f32 * BASE_STRENGTH_PER_LEVEL)
+            .min(MAX_BASE_STRENGTH);
+        let direction = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+        Wind {
+            base: strength * direction,
+            gusty: true,
+            rng,
+            ..Self::calm()
+        }
+    }
+
+    pub fn is_calm(&self) -> bool {
+        self.base == 0.0
+    }
+
+    /// Advances the wind by `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        if self.is_calm() {
+            return;
+        }
+        self.time += dt;
+        if self.gust_left > 0.0 {
+            self.gust_left -= dt;
+            if self.gust_left <= 0.0 {
+                self.gust = 0.0;
+            }
+        } else if self.gusty && self.rng.gen_bool(GUST_CHANCE) {
+            self.gust = self.rng.gen_range(-GUST_STRENGTH..=GUST_STRENGTH);
+            self.gust_left = self.rng.gen_range(GUST_DURATION.0..GUST_DURATION.1);
+        }
+    }
+
+    /// Current horizontal acceleration in m/s².
+    pub fn force(&self) -> f32 {
+        let swing = (self.time * std::f32::consts::TAU / OSCILLATION_PERIOD).sin();
+        self.base * (1.0 + 0.5 * swing) + self.gust
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lander::{LanderInput, LunarLander};
+    use crate::physics::PhysicsConfig;
+    /// Horizontal speed after flying weightless through `wind` for `seconds`.
+    fn drift_speed(mut wind: Wind, seconds: f32) -> f32 {
+        let mut lander = LunarLander::with_physics(400.0, 200.0, PhysicsConfig::lunar());
+        let dt = lander.physics.dt;
+        for _ in 0..(seconds / dt).round() as u32 {
+            wind.update(dt);
+            let input = LanderInput {
+                wind: wind.force(),
+                ..Default::default()
+            };
+            lander.step(input, dt, 0.0);
+        }
+        lander.velocity.x
+    }
+
+    #[test]
+    fn calm_air_leaves_velocity_alone() {
+        assert_eq!(drift_speed(Wind::calm(), 5.0), 0.0);
+    }
+
+    #[test]
+    fn steady_wind_integrates_to_strength_times_time() {
+        // Whole swings average out, leaving just the base push
+        let speed = drift_speed(Wind::steady(0.4), 2.0 * OSCILLATION_PERIOD);
+        assert!(
+            (speed - 0.4 * 2.0 * OSCILLATION_PERIOD).abs() < 0.02,
+            "{}",
+            speed
+        );
+
+        let speed = drift_speed(Wind::steady(-0.4), OSCILLATION_PERIOD);
+        assert!((speed + 0.4 * OSCILLATION_PERIOD).abs() < 0.02, "{}", speed);
+    }
+}